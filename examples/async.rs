@@ -1,4 +1,4 @@
-use pocket::{Client, non_blocking::ExtendAuth};
+use pocket::Client;
 use serde_json::Value;
 
 #[tokio::main]
@@ -20,6 +20,4 @@ async fn main() {
         .get_list::<Value>(Default::default())
         .await
         .unwrap();
-
-    pocket.authenticate(token)
-}
\ No newline at end of file
+}
@@ -0,0 +1,211 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{
+    Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Ident, Lit, LitStr, Meta, Token,
+    parse_macro_input, punctuated::Punctuated,
+};
+
+const SYSTEM_FIELDS: [(&str, &str); 5] = [
+    ("ID", "id"),
+    ("COLLECTION_ID", "collectionId"),
+    ("COLLECTION_NAME", "collectionName"),
+    ("CREATED", "created"),
+    ("UPDATED", "updated"),
+];
+
+/// Implements `PocketRecord` and adds a `pub const` holding the serialized name of every field,
+/// e.g. `Post::TITLE`, plus `ID`, `COLLECTION_ID`, `COLLECTION_NAME`, `CREATED` and `UPDATED` for
+/// the system fields.
+///
+/// The collection defaults to the snake_case type name and can be set with
+/// `#[pocket(collection = "...")]`. Serde `rename`, `rename_all`, `skip` and `flatten` are
+/// respected.
+///
+/// Only the name constants of the system fields are generated, not the fields themselves. Wrap the
+/// struct in `pocket::TypedRecord<T>` to read them from responses.
+#[proc_macro_derive(PocketRecord, attributes(pocket))]
+pub fn derive_pocket_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "PocketRecord can only be derived for structs",
+        ));
+    };
+
+    let collection = match attribute_value(&input.attrs, "pocket", "collection")? {
+        Some(collection) => collection,
+        None => LitStr::new(&snake_case(&input.ident.to_string()), input.ident.span()),
+    };
+    let rename_all = attribute_value(&input.attrs, "serde", "rename_all")?;
+
+    let mut constants = Vec::<(String, String)>::new();
+    if let Fields::Named(fields) = &data.fields {
+        for field in &fields.named {
+            if has_flag(&field.attrs, "serde", &["skip", "flatten"])? {
+                continue;
+            }
+
+            let field_ident = field.ident.as_ref().expect("named field");
+            let ident = field_ident.to_string();
+            let ident = ident.trim_start_matches("r#");
+            if ident == "collection" {
+                return Err(syn::Error::new_spanned(
+                    field_ident,
+                    "a field named `collection` would shadow `PocketRecord::COLLECTION`; rename \
+                     the field and keep its name with `#[serde(rename = \"collection\")]`",
+                ));
+            }
+            let name = match attribute_value(&field.attrs, "serde", "rename")? {
+                Some(rename) => rename.value(),
+                None => match &rename_all {
+                    Some(rule) => rename(ident, &rule.value())
+                        .ok_or_else(|| syn::Error::new_spanned(rule, "unknown rename_all rule"))?,
+                    None => ident.to_string(),
+                },
+            };
+            constants.push((ident.to_uppercase(), name));
+        }
+    }
+
+    for (constant, name) in SYSTEM_FIELDS {
+        if !constants.iter().any(|(existing, _)| existing == constant) {
+            constants.push((constant.to_string(), name.to_string()));
+        }
+    }
+
+    let constants = constants.into_iter().map(|(constant, name)| {
+            let constant = Ident::new(&constant, Span::call_site());
+            quote! { pub const #constant: &'static str = #name; }
+        });
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::pocket::PocketRecord for #ident #ty_generics #where_clause {
+            const COLLECTION: &'static str = #collection;
+        }
+
+        #[allow(dead_code)]
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #(#constants)*
+        }
+    })
+}
+
+fn metas(attr: &Attribute) -> syn::Result<Punctuated<Meta, Token![,]>> {
+    attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+}
+
+fn attribute_value(attrs: &[Attribute], attr: &str, key: &str) -> syn::Result<Option<LitStr>> {
+    for attr in attrs.iter().filter(|a| a.path().is_ident(attr)) {
+        for meta in metas(attr)? {
+            if let Meta::NameValue(meta) = meta
+                && meta.path.is_ident(key)
+            {
+                return match meta.value {
+                    Expr::Lit(ExprLit { lit: Lit::Str(value), .. }) => Ok(Some(value)),
+                    value => Err(syn::Error::new_spanned(value, "expected a string literal")),
+                };
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn has_flag(attrs: &[Attribute], attr: &str, flags: &[&str]) -> syn::Result<bool> {
+    for attr in attrs.iter().filter(|a| a.path().is_ident(attr)) {
+        for meta in metas(attr)? {
+            if let Meta::Path(path) = meta
+                && flags.iter().any(|flag| path.is_ident(flag))
+            {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Split words like heck does, keeping acronym runs together: `HTTPLog` becomes `http_log`.
+fn snake_case(value: &str) -> String {
+    let chars = value.chars().collect::<Vec<_>>();
+    let mut result = String::new();
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_lower) {
+                result.push('_');
+            }
+        }
+        result.extend(ch.to_lowercase());
+    }
+    result
+}
+
+fn rename(field: &str, rule: &str) -> Option<String> {
+    let words = field.split('_').filter(|word| !word.is_empty());
+    let capitalize = |word: &str| {
+        let mut chars = word.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+            .unwrap_or_default()
+    };
+
+    Some(match rule {
+        "lowercase" => field.to_lowercase(),
+        "UPPERCASE" => field.to_uppercase(),
+        "snake_case" => field.to_string(),
+        "SCREAMING_SNAKE_CASE" => field.to_uppercase(),
+        "kebab-case" => field.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field.to_uppercase().replace('_', "-"),
+        "PascalCase" => words.map(capitalize).collect(),
+        "camelCase" => words
+            .enumerate()
+            .map(|(i, word)| if i == 0 { word.to_string() } else { capitalize(word) })
+            .collect(),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snake_cases_type_names() {
+        assert_eq!(snake_case("Post"), "post");
+        assert_eq!(snake_case("UserProfile"), "user_profile");
+        assert_eq!(snake_case("HTTPLog"), "http_log");
+        assert_eq!(snake_case("APIKey"), "api_key");
+        assert_eq!(snake_case("UserID"), "user_id");
+        assert_eq!(snake_case("Post2Comment"), "post2_comment");
+    }
+
+    #[test]
+    fn rejects_collection_field() {
+        let input = syn::parse_quote! {
+            struct Post {
+                collection: String,
+            }
+        };
+        let err = expand(input).unwrap_err();
+        assert!(err.to_string().contains("shadow `PocketRecord::COLLECTION`"));
+    }
+
+    #[test]
+    fn renames_fields() {
+        assert_eq!(rename("created_at", "camelCase").as_deref(), Some("createdAt"));
+        assert_eq!(rename("created_at", "PascalCase").as_deref(), Some("CreatedAt"));
+        assert_eq!(rename("created_at", "kebab-case").as_deref(), Some("created-at"));
+        assert_eq!(rename("created_at", "bogus"), None);
+    }
+}
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthMethods {
+    #[serde(default)]
+    pub password: PasswordAuth,
+    #[serde(default)]
+    pub oauth2: OAuth2Auth,
+    #[serde(default)]
+    pub mfa: DurationAuth,
+    #[serde(default)]
+    pub otp: DurationAuth,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordAuth {
+    pub enabled: bool,
+    #[serde(default)]
+    pub identity_fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuth2Auth {
+    pub enabled: bool,
+    #[serde(default)]
+    pub providers: Vec<AuthProvider>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DurationAuth {
+    pub enabled: bool,
+    #[serde(default)]
+    pub duration: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthProvider {
+    pub name: String,
+    #[serde(default)]
+    pub display_name: String,
+    pub state: String,
+    #[serde(rename = "authURL")]
+    pub auth_url: String,
+    pub code_verifier: String,
+    #[serde(default)]
+    pub code_challenge: String,
+    #[serde(default)]
+    pub code_challenge_method: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuth2Meta {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub email: String,
+    #[serde(default, rename = "avatarURL")]
+    pub avatar_url: String,
+    #[serde(default)]
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: String,
+    #[serde(default)]
+    pub expiry: Option<String>,
+    #[serde(default)]
+    pub is_new: bool,
+    #[serde(default)]
+    pub raw_user: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmPasswordReset {
+    pub token: String,
+    pub password: String,
+    pub password_confirm: String,
+}
+
+impl ConfirmPasswordReset {
+    pub fn new(token: impl Into<String>, password: impl Into<String>) -> Self {
+        let password = password.into();
+        Self {
+            token: token.into(),
+            password_confirm: password.clone(),
+            password,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmEmailChange {
+    pub token: String,
+    pub password: String,
+}
+
+impl ConfirmEmailChange {
+    pub fn new(token: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            password: password.into(),
+        }
+    }
+}
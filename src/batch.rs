@@ -1,10 +1,9 @@
-use reqwest::{Body, multipart::{Form, Part}};
 use serde::{Serialize, de::DeserializeOwned};
 use serde_json::json;
-use tokio_util::codec::{BytesCodec, FramedRead};
 
 use crate::{
-    BatchRequest, CreateOptions, Error, PocketBaseError, UpdateOptions, client::PocketBaseClient, files::File,
+    BatchRequest, CreateOptions, Error, UpdateOptions, client::PocketBaseClient, error::{decode, encode},
+    files::{self, File},
 };
 
 pub struct BatchBuilder<'p, P: PocketBaseClient> {
@@ -33,55 +32,23 @@ impl<'p, P: PocketBaseClient> BatchBuilder<'p, P> {
                     ctx
                 });
 
-        let mut form = Form::new();
-        form = form.text(
-            "@jsonPayload",
-            serde_json::to_string(&json!({ "requests": requests }))?,
-        );
-
-        for (i, files) in files.into_iter().enumerate() {
-            if let Some(files) = files {
-                for (name, file) in files {
-                    match file {
-                        File::Path(path) => {
-                            let file = tokio::fs::File::open(&path).await?;
-                            let stream = FramedRead::new(file, BytesCodec::new());
-
-                            form = form
-                                .part(
-                                    format!("requests.{i}.{name}"),
-                                    Part::stream(Body::wrap_stream(stream))
-                                        .file_name(path.file_name().unwrap().to_string_lossy().to_string())
-                                        .mime_str(mime_to_ext::ext_to_mime(path.extension().unwrap().to_string_lossy().as_ref()).unwrap())?
-                                );
-                        },
-                        File::Raw {
-                            filename,
-                            mime,
-                            bytes,
-                        } => form = form
-                            .part(
-                                format!("requests.{i}.{name}"),
-                                Part::bytes(bytes.clone())
-                                    .file_name(filename.to_string())
-                                    .mime_str(&mime)?
-                            ),
-                    }
-                }
-            }
-        }
-
-        let res = self
-            .pocketbase
-            .post("/api/batch")
-            .multipart(form)
-            .send()
-            .await?;
+        let request = files::body(
+            self.pocketbase.post("/api/batch")?,
+            &json!({ "requests": requests }),
+            files.into_iter().enumerate().flat_map(|(i, files)| {
+                files
+                    .into_iter()
+                    .flatten()
+                    .map(move |(name, file)| (format!("requests.{i}.{name}"), file))
+            }),
+        )
+        .await?;
+        let res = self.pocketbase.send(request).await?;
 
         if !res.status().is_success() {
-            return Err(res.json::<PocketBaseError>().await?.into());
+            return Err(Error::from_response(res).await);
         }
-        Ok(res.json::<T>().await?)
+        decode::<T>(res).await
     }
 }
 
@@ -102,7 +69,7 @@ where
     ) -> Result<(), Error> {
         self.batch.requests.push(BatchRequest::Create {
             collection: self.identifier.to_string(),
-            record: serde_json::to_value(record)?,
+            record: encode(record)?,
             files: files.into_iter().collect(),
             options,
         });
@@ -119,7 +86,7 @@ where
         self.batch.requests.push(BatchRequest::Update {
             collection: self.identifier.to_string(),
             id: id.to_string(),
-            record: serde_json::to_value(record)?,
+            record: encode(record)?,
             files: files.into_iter().collect(),
             options,
         });
@@ -1,12 +1,20 @@
-use chrono::{DateTime, TimeZone, Utc};
-use reqwest::RequestBuilder;
-use serde_json::Value;
-use std::collections::BTreeMap;
-
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, TimeDelta, Utc};
+use reqwest::{
+    RequestBuilder, Response, StatusCode,
+    header::{AUTHORIZATION, HeaderValue},
+};
+use serde_json::{Value, json};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, PoisonError, RwLock},
+    time::Duration,
+};
+use tokio::sync::Mutex;
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use url::Url;
 
-use crate::{Claims, Error, FilesBuilder, Health, batch::BatchBuilder, collection::CollectionBuilder, error::FieldError};
+use crate::{Claims, Error, FilesBuilder, Health, batch::BatchBuilder, collection::CollectionBuilder, collections::CollectionsBuilder, error::{FieldError, decode}, realtime::Realtime, record::PocketRecord, store::AuthStore};
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Token {
@@ -16,19 +24,60 @@ pub struct Token {
     pub expires: DateTime<Utc>,
     pub refreshable: bool,
     pub ty: String,
+    /// The auth record returned alongside the token, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record: Option<Value>,
 }
 impl Token {
+    pub(crate) fn from_auth(
+        collection: impl std::fmt::Display,
+        auth: String,
+        record: Value,
+    ) -> Result<Self, Error> {
+        let claims = unsafe { Claims::decode_unsafe(&auth)? };
+        let user = match record.get("id") {
+            Some(Value::String(id)) => id.clone(),
+            Some(_) => return Err(Error::custom("auth record has a non string `id`")),
+            None => claims.id,
+        };
+
+        Ok(Self {
+            user,
+            collection: collection.to_string(),
+            expires: DateTime::from_timestamp(claims.exp, 0).ok_or_else(|| {
+                Error::custom(format!("auth token has an invalid expiration `{}`", claims.exp))
+            })?,
+            auth,
+            refreshable: claims.refreshable,
+            ty: claims.ty,
+            record: Some(record),
+        })
+    }
+
     pub fn is_expired(&self) -> bool {
         self.expires < Utc::now()
     }
+
+    pub fn verify(&self, secret: impl AsRef<[u8]>) -> Result<Claims, Error> {
+        let claims = Claims::decode(&self.auth, secret)?;
+        if claims.ty != "auth" {
+            return Err(Error::custom(format!("expected an auth token, found `{}`", claims.ty)));
+        }
+        if claims.id != self.user {
+            return Err(Error::custom("token does not belong to the authorized user"));
+        }
+        Ok(claims)
+    }
 }
 
 pub trait PocketBaseClient {
     fn base_uri(&self) -> String;
-    fn get(&self, uri: impl AsRef<str>) -> RequestBuilder;
-    fn post(&self, uri: impl AsRef<str>) -> RequestBuilder;
-    fn patch(&self, uri: impl AsRef<str>) -> RequestBuilder;
-    fn delete(&self, uri: impl AsRef<str>) -> RequestBuilder;
+    fn get(&self, uri: impl AsRef<str>) -> Result<RequestBuilder, Error>;
+    fn post(&self, uri: impl AsRef<str>) -> Result<RequestBuilder, Error>;
+    fn put(&self, uri: impl AsRef<str>) -> Result<RequestBuilder, Error>;
+    fn patch(&self, uri: impl AsRef<str>) -> Result<RequestBuilder, Error>;
+    fn delete(&self, uri: impl AsRef<str>) -> Result<RequestBuilder, Error>;
+    fn send(&self, request: RequestBuilder) -> impl Future<Output = Result<Response, Error>> + Send;
 }
 
 pub struct Client {
@@ -37,14 +86,31 @@ pub struct Client {
 }
 impl Client {
     pub fn new(base_uri: impl AsRef<str>) -> Self {
-        Self {
+        Self::try_new(base_uri).expect("invalid pocketbase base uri")
+    }
+
+    pub fn try_new(base_uri: impl AsRef<str>) -> Result<Self, Error> {
+        Ok(Self {
             client: Default::default(),
-            base_uri: Url::parse(base_uri.as_ref()).unwrap(),
-        }
+            base_uri: Url::parse(base_uri.as_ref())?,
+        })
     }
 
     pub fn authorize(&self, token: Token) -> AuthorizedClient {
-        AuthorizedClient::new(self.base_uri.clone(), token)
+        AuthorizedClient::from_parts(self.base_uri.clone(), token)
+    }
+
+    pub fn authorize_from_store(
+        &self,
+        store: impl AuthStore + 'static,
+    ) -> Result<Option<AuthorizedClient>, Error> {
+        let Some(token) = store.load()?.filter(|token| !token.is_expired()) else {
+            return Ok(None);
+        };
+
+        let mut client = self.authorize(token);
+        client.store = Some(Arc::new(store));
+        Ok(Some(client))
     }
 
     pub fn collection<'c, I: std::fmt::Display>(
@@ -57,6 +123,10 @@ impl Client {
         }
     }
 
+    pub fn records<'c, R: PocketRecord>(&'c self) -> CollectionBuilder<'c, Self, &'static str> {
+        self.collection(R::COLLECTION)
+    }
+
     pub fn create_batch<'c>(&'c self) -> BatchBuilder<'c, Self> {
         BatchBuilder {
             pocketbase: self,
@@ -64,6 +134,14 @@ impl Client {
         }
     }
 
+    pub fn realtime<'c>(&'c self) -> Realtime<'c, Self> {
+        Realtime {
+            pocketbase: self,
+            topics: Default::default(),
+            backoff: Default::default(),
+        }
+    }
+
     pub fn files<'c>(&'c self) -> FilesBuilder<'c> {
         FilesBuilder {
             base_uri: &self.base_uri,
@@ -71,12 +149,7 @@ impl Client {
     }
 
     pub async fn health(&self) -> Result<Health, Error> {
-        Ok(self
-            .get("/api/health")
-            .send()
-            .await?
-            .json()
-            .await?)
+        decode(self.send(self.get("/api/health")?).await?).await
     }
 }
 
@@ -85,26 +158,38 @@ impl PocketBaseClient for Client {
         self.base_uri.to_string()
     }
 
-    fn get(&self, uri: impl AsRef<str>) -> RequestBuilder {
-        self.client.get(self.base_uri.join(uri.as_ref()).unwrap())
+    fn get(&self, uri: impl AsRef<str>) -> Result<RequestBuilder, Error> {
+        Ok(self.client.get(self.base_uri.join(uri.as_ref())?))
     }
 
-    fn post(&self, uri: impl AsRef<str>) -> RequestBuilder {
-        self.client.post(self.base_uri.join(uri.as_ref()).unwrap())
+    fn post(&self, uri: impl AsRef<str>) -> Result<RequestBuilder, Error> {
+        Ok(self.client.post(self.base_uri.join(uri.as_ref())?))
     }
 
-    fn patch(&self, uri: impl AsRef<str>) -> RequestBuilder {
-        self.client.patch(self.base_uri.join(uri.as_ref()).unwrap())
+    fn put(&self, uri: impl AsRef<str>) -> Result<RequestBuilder, Error> {
+        Ok(self.client.put(self.base_uri.join(uri.as_ref())?))
     }
 
-    fn delete(&self, uri: impl AsRef<str>) -> RequestBuilder {
-        self.client.delete(self.base_uri.join(uri.as_ref()).unwrap())
+    fn patch(&self, uri: impl AsRef<str>) -> Result<RequestBuilder, Error> {
+        Ok(self.client.patch(self.base_uri.join(uri.as_ref())?))
+    }
+
+    fn delete(&self, uri: impl AsRef<str>) -> Result<RequestBuilder, Error> {
+        Ok(self.client.delete(self.base_uri.join(uri.as_ref())?))
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Response, Error> {
+        Ok(request.send().await?)
     }
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(untagged)]
 pub enum AuthResult {
+    Mfa {
+        #[serde(rename = "mfaId")]
+        mfa_id: String,
+    },
     Error {
         status: u16,
         #[serde(default)]
@@ -115,67 +200,177 @@ pub enum AuthResult {
     Success {
         token: String,
         record: Value,
+        #[serde(default)]
+        meta: Option<Value>,
     },
 }
 
+impl AuthResult {
+    pub(crate) async fn from_response(res: Response) -> Result<Self, Error> {
+        if res.status().is_success() {
+            return decode::<AuthResult>(res).await;
+        }
+
+        let status = res.status();
+        let body = res.bytes().await?;
+        Ok(serde_json::from_slice::<AuthResult>(&body).unwrap_or_else(|_| AuthResult::Error {
+            status: status.as_u16(),
+            message: status.canonical_reason().map(ToString::to_string),
+            data: Default::default(),
+        }))
+    }
+}
+
+#[derive(Clone)]
 pub struct AuthorizedClient {
     pub base_uri: Url,
-    token: Token,
+    token: Arc<RwLock<Token>>,
+    refreshing: Arc<Mutex<()>>,
+    auto_refresh: Option<TimeDelta>,
+    store: Option<Arc<dyn AuthStore>>,
     client: reqwest::Client,
 }
 
 impl AuthorizedClient {
     pub fn new(base_url: impl AsRef<str>, token: Token) -> Self {
+        Self::try_new(base_url, token).expect("invalid pocketbase base uri")
+    }
+
+    pub fn try_new(base_url: impl AsRef<str>, token: Token) -> Result<Self, Error> {
+        Ok(Self::from_parts(Url::parse(base_url.as_ref())?, token))
+    }
+
+    pub(crate) fn from_parts(base_uri: Url, token: Token) -> Self {
         Self {
-            base_uri: Url::parse(base_url.as_ref()).unwrap(),
+            base_uri,
             client: Default::default(),
-            token
+            token: Arc::new(RwLock::new(token)),
+            refreshing: Default::default(),
+            auto_refresh: None,
+            store: None,
+        }
+    }
+
+    pub fn with_store(mut self, store: impl AuthStore + 'static) -> Result<Self, Error> {
+        store.save(&self.token())?;
+        self.store = Some(Arc::new(store));
+        Ok(self)
+    }
+
+    pub fn logout(self) -> Result<Client, Error> {
+        if let Some(store) = &self.store {
+            store.clear()?;
+        }
+
+        Ok(Client {
+            base_uri: self.base_uri,
+            client: self.client,
+        })
+    }
+
+    pub fn auto_refresh(mut self, leeway: TimeDelta) -> Self {
+        self.auto_refresh = Some(leeway);
+        self
+    }
+
+    pub fn token(&self) -> Token {
+        self.token.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// The authenticated record, or `None` if the client was built from a token without one.
+    pub fn record<T: DeserializeOwned>(&self) -> Result<Option<T>, Error> {
+        let token = self.token.read().unwrap_or_else(PoisonError::into_inner);
+        match &token.record {
+            Some(record) => Ok(Some(T::deserialize(record)?)),
+            None => Ok(None),
         }
     }
 
-    pub fn token(self) -> Token {
-        self.token
+    fn auth(&self) -> String {
+        self.token.read().unwrap_or_else(PoisonError::into_inner).auth.clone()
     }
 
     pub fn is_expired(&self) -> bool {
-        self.token.is_expired()
+        self.token.read().unwrap_or_else(PoisonError::into_inner).is_expired()
+    }
+
+    pub async fn refresh(&self) -> Result<(), Error> {
+        let _guard = self.refreshing.lock().await;
+        self.refresh_token().await
     }
 
-    pub async fn refresh(&mut self) -> Result<(), Error> {
+    async fn refresh_stale(&self, stale: &str) -> Result<(), Error> {
+        let _guard = self.refreshing.lock().await;
+        if self.auth() != stale {
+            return Ok(());
+        }
+        self.refresh_token().await
+    }
+
+    async fn refresh_token(&self) -> Result<(), Error> {
         let Token {
             auth, collection, ..
-        } = &self.token;
+        } = self.token();
 
-        let result = self
-            .post(format!("/api/collections/{collection}/auth-refresh"))
-            .header("Authorization", auth)
-            .send()
-            .await?
-            .json::<AuthResult>()
-            .await?;
+        let result = AuthResult::from_response(
+            self.client
+                .post(self.base_uri.join(&format!("/api/collections/{collection}/auth-refresh"))?)
+                .header(AUTHORIZATION, auth)
+                .send()
+                .await?,
+        )
+        .await?;
 
         match result {
-            AuthResult::Error { message, .. } => {
-                return Err(Error::Custom(
-                    message.unwrap_or("failed to authenticate user".into()),
-                ));
+            AuthResult::Error { status, message, data } => {
+                return Err(Error::Authorization {
+                    status,
+                    message: message.unwrap_or("failed to refresh token".into()),
+                    data,
+                });
             }
-            AuthResult::Success { token, record } => {
-                let claims = unsafe { Claims::decode_unsafe(&token)? };
-                self.token = Token {
-                    user: record.as_object().unwrap().get("id").unwrap().as_str().unwrap().to_string(),
-                    collection: collection.clone(),
-                    auth: token,
-                    refreshable: claims.refreshable,
-                    ty: claims.ty,
-                    expires: Utc.timestamp_opt(claims.exp, 0).unwrap(),
-                };
+            AuthResult::Mfa { mfa_id } => return Err(Error::MfaRequired { mfa_id }),
+            AuthResult::Success { token, record, .. } => {
+                let token = Token::from_auth(collection, token, record)?;
+                if let Some(store) = &self.store {
+                    store.save(&token)?;
+                }
+                *self.token.write().unwrap_or_else(PoisonError::into_inner) = token;
             }
         }
 
         Ok(())
     }
 
+    pub async fn impersonate(
+        &self,
+        collection: impl std::fmt::Display,
+        record_id: impl std::fmt::Display,
+        duration: Duration,
+    ) -> Result<AuthorizedClient, Error> {
+        let request = self
+            .post(format!("/api/collections/{collection}/impersonate/{record_id}"))?
+            .json(&json!({ "duration": duration.as_secs() }));
+        let res = self.send(request).await?;
+
+        if matches!(res.status(), StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) {
+            return Err(Error::Unauthorized);
+        }
+
+        match AuthResult::from_response(res).await? {
+            AuthResult::Error { status, message, data } => Err(Error::Authorization {
+                status,
+                message: message.unwrap_or("failed to impersonate user".into()),
+                data,
+            }),
+            AuthResult::Mfa { mfa_id } => Err(Error::MfaRequired { mfa_id }),
+            AuthResult::Success { token, record, .. } => Ok(AuthorizedClient::from_parts(
+                self.base_uri.clone(),
+                Token::from_auth(collection, token, record)?,
+            )),
+        }
+    }
+
     pub fn collection<'c, I: std::fmt::Display>(
         &'c self,
         identifier: I,
@@ -186,6 +381,10 @@ impl AuthorizedClient {
         }
     }
 
+    pub fn records<'c, R: PocketRecord>(&'c self) -> CollectionBuilder<'c, Self, &'static str> {
+        self.collection(R::COLLECTION)
+    }
+
     pub fn create_batch<'c>(&'c self) -> BatchBuilder<'c, Self> {
         BatchBuilder {
             pocketbase: self,
@@ -193,6 +392,18 @@ impl AuthorizedClient {
         }
     }
 
+    pub fn realtime<'c>(&'c self) -> Realtime<'c, Self> {
+        Realtime {
+            pocketbase: self,
+            topics: Default::default(),
+            backoff: Default::default(),
+        }
+    }
+
+    pub fn collections<'c>(&'c self) -> CollectionsBuilder<'c, Self> {
+        CollectionsBuilder { pocketbase: self }
+    }
+
     pub fn files<'c>(&'c self) -> FilesBuilder<'c> {
         FilesBuilder {
             base_uri: &self.base_uri,
@@ -200,12 +411,7 @@ impl AuthorizedClient {
     }
 
     pub async fn health(&self) -> Result<Health, Error> {
-        Ok(self
-            .get("/api/health")
-            .send()
-            .await?
-            .json()
-            .await?)
+        decode(self.send(self.get("/api/health")?).await?).await
     }
 }
 
@@ -214,23 +420,67 @@ impl PocketBaseClient for AuthorizedClient {
         self.base_uri.to_string()
     }
 
-    fn get(&self, uri: impl AsRef<str>) -> RequestBuilder {
-        self.client.get(self.base_uri.join(uri.as_ref()).unwrap())
-            .header("Authorization", &self.token.auth)
+    fn get(&self, uri: impl AsRef<str>) -> Result<RequestBuilder, Error> {
+        Ok(self.client.get(self.base_uri.join(uri.as_ref())?)
+            .header(AUTHORIZATION, self.auth()))
     }
 
-    fn post(&self, uri: impl AsRef<str>) -> RequestBuilder {
-        self.client.post(self.base_uri.join(uri.as_ref()).unwrap())
-            .header("Authorization", &self.token.auth)
+    fn post(&self, uri: impl AsRef<str>) -> Result<RequestBuilder, Error> {
+        Ok(self.client.post(self.base_uri.join(uri.as_ref())?)
+            .header(AUTHORIZATION, self.auth()))
     }
 
-    fn patch(&self, uri: impl AsRef<str>) -> RequestBuilder {
-        self.client.patch(self.base_uri.join(uri.as_ref()).unwrap())
-            .header("Authorization", &self.token.auth)
+    fn put(&self, uri: impl AsRef<str>) -> Result<RequestBuilder, Error> {
+        Ok(self.client.put(self.base_uri.join(uri.as_ref())?)
+            .header(AUTHORIZATION, self.auth()))
     }
 
-    fn delete(&self, uri: impl AsRef<str>) -> RequestBuilder {
-        self.client.delete(self.base_uri.join(uri.as_ref()).unwrap())
-            .header("Authorization", &self.token.auth)
+    fn patch(&self, uri: impl AsRef<str>) -> Result<RequestBuilder, Error> {
+        Ok(self.client.patch(self.base_uri.join(uri.as_ref())?)
+            .header(AUTHORIZATION, self.auth()))
     }
-}
\ No newline at end of file
+
+    fn delete(&self, uri: impl AsRef<str>) -> Result<RequestBuilder, Error> {
+        Ok(self.client.delete(self.base_uri.join(uri.as_ref())?)
+            .header(AUTHORIZATION, self.auth()))
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Response, Error> {
+        let Some(leeway) = self.auto_refresh else {
+            return Ok(request.send().await?);
+        };
+
+        let token = self.token();
+        if token.refreshable && token.expires - leeway <= Utc::now() {
+            self.refresh_stale(&token.auth).await?;
+        }
+
+        let (client, request) = request.build_split();
+        let mut request = request?;
+        // A failed login answers 401 too; retrying it would only repeat the attempt
+        let retry = match is_auth_flow(request.url()) {
+            true => None,
+            false => request.try_clone(),
+        };
+
+        let auth = self.auth();
+        request.headers_mut().insert(AUTHORIZATION, HeaderValue::from_str(&auth).map_err(Error::custom)?);
+        let res = client.execute(request).await?;
+
+        match retry {
+            Some(mut retry) if res.status() == StatusCode::UNAUTHORIZED && token.refreshable => {
+                self.refresh_stale(&auth).await?;
+                retry.headers_mut().insert(AUTHORIZATION, HeaderValue::from_str(&self.auth()).map_err(Error::custom)?);
+                Ok(client.execute(retry).await?)
+            }
+            _ => Ok(res),
+        }
+    }
+}
+
+/// Whether the request authenticates a record, e.g. `auth-with-password` or `auth-with-otp`.
+fn is_auth_flow(url: &Url) -> bool {
+    url.path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .is_some_and(|action| action.starts_with("auth-with-"))
+}
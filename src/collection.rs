@@ -1,13 +1,13 @@
-use chrono::{TimeZone, Utc};
-use reqwest::{Body, multipart::{Form, Part}};
-use serde::{Serialize, de::DeserializeOwned};
+use futures_util::{Stream, TryStreamExt};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::{Value, json};
-use tokio_util::codec::{BytesCodec, FramedRead};
 
 use crate::{
-    AuthorizedClient, Claims, CreateOptions, Error, ListOptions, Paginated, PocketBaseError, Token, UpdateOptions, ViewOptions, client::{AuthResult, PocketBaseClient}, files::File
+    AuthorizedClient, auth::{AuthMethods, ConfirmEmailChange, ConfirmPasswordReset, OAuth2Meta}, CreateOptions, Error, error::{decode, encode}, ListOptions, Paginated, Token, UpdateOptions, ViewOptions, client::{AuthResult, PocketBaseClient}, files::{self, File}, query::{self, Query}, realtime::{self, Subscription}
 };
 
+const DEFAULT_PER_PAGE: usize = 30;
+
 pub struct CollectionBuilder<'c, P: PocketBaseClient, I: std::fmt::Display> {
     pub(crate) pocketbase: &'c P,
     pub(crate) identifier: I,
@@ -23,46 +23,205 @@ where
         identifier: &str,
         secret: &str,
     ) -> Result<AuthorizedClient, Error> {
-        let result = self
+        let (client, _) = self
+            .authenticate(
+                "auth-with-password",
+                json!({
+                    "identity": identifier,
+                    "password": secret,
+                }),
+            )
+            .await?;
+        Ok(client)
+    }
+
+    pub async fn auth_with_password_mfa(
+        &self,
+        identifier: &str,
+        secret: &str,
+        mfa_id: &str,
+    ) -> Result<AuthorizedClient, Error> {
+        let (client, _) = self
+            .authenticate(
+                "auth-with-password",
+                json!({
+                    "identity": identifier,
+                    "password": secret,
+                    "mfaId": mfa_id,
+                }),
+            )
+            .await?;
+        Ok(client)
+    }
+
+    pub async fn request_otp(&self, email: &str) -> Result<String, Error> {
+        let request = self
             .pocketbase
-            .post(format!(
-                "/api/collections/{}/auth-with-password",
-                self.identifier,
-            ))
-            .json(&json!({
-                "identity": identifier,
-                "password": secret,
-            }))
-            .send()
-            .await?
-            .json::<AuthResult>()
-            .await
-            .unwrap();
+            .post(format!("/api/collections/{}/request-otp", self.identifier))?
+            .json(&json!({ "email": email }));
+        let res = self.pocketbase.send(request).await?;
+
+        if !res.status().is_success() {
+            return Err(Error::from_response(res).await);
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct OtpResponse {
+            otp_id: String,
+        }
+        Ok(decode::<OtpResponse>(res).await?.otp_id)
+    }
+
+    pub async fn auth_with_otp(
+        &self,
+        otp_id: &str,
+        password: &str,
+    ) -> Result<AuthorizedClient, Error> {
+        let (client, _) = self
+            .authenticate(
+                "auth-with-otp",
+                json!({
+                    "otpId": otp_id,
+                    "password": password,
+                }),
+            )
+            .await?;
+        Ok(client)
+    }
+
+    pub async fn auth_with_otp_mfa(
+        &self,
+        otp_id: &str,
+        password: &str,
+        mfa_id: &str,
+    ) -> Result<AuthorizedClient, Error> {
+        let (client, _) = self
+            .authenticate(
+                "auth-with-otp",
+                json!({
+                    "otpId": otp_id,
+                    "password": password,
+                    "mfaId": mfa_id,
+                }),
+            )
+            .await?;
+        Ok(client)
+    }
+
+    pub async fn list_auth_methods(&self) -> Result<AuthMethods, Error> {
+        let request = self
+            .pocketbase
+            .get(format!("/api/collections/{}/auth-methods", self.identifier))?;
+        let res = self.pocketbase.send(request).await?;
+
+        if !res.status().is_success() {
+            return Err(Error::from_response(res).await);
+        }
+        decode::<AuthMethods>(res).await
+    }
+
+    pub async fn auth_with_oauth2_code(
+        &self,
+        provider: &str,
+        code: &str,
+        code_verifier: &str,
+        redirect_url: &str,
+        create_data: Option<Value>,
+    ) -> Result<(AuthorizedClient, OAuth2Meta), Error> {
+        let (client, meta) = self
+            .authenticate(
+                "auth-with-oauth2",
+                json!({
+                    "provider": provider,
+                    "code": code,
+                    "codeVerifier": code_verifier,
+                    "redirectURL": redirect_url,
+                    "createData": create_data,
+                }),
+            )
+            .await?;
+
+        let meta = match meta {
+            Some(meta) => serde_json::from_value(meta)?,
+            None => OAuth2Meta::default(),
+        };
+        Ok((client, meta))
+    }
+
+    pub async fn request_verification(&self, email: &str) -> Result<(), Error> {
+        self.action("request-verification", json!({ "email": email })).await
+    }
+
+    pub async fn confirm_verification(&self, token: &str) -> Result<(), Error> {
+        self.action("confirm-verification", json!({ "token": token })).await
+    }
+
+    pub async fn request_password_reset(&self, email: &str) -> Result<(), Error> {
+        self.action("request-password-reset", json!({ "email": email })).await
+    }
+
+    pub async fn confirm_password_reset(&self, confirm: ConfirmPasswordReset) -> Result<(), Error> {
+        self.action("confirm-password-reset", encode(confirm)?).await
+    }
+
+    pub async fn request_email_change(&self, new_email: &str) -> Result<(), Error> {
+        self.action("request-email-change", json!({ "newEmail": new_email })).await
+    }
+
+    pub async fn confirm_email_change(&self, confirm: ConfirmEmailChange) -> Result<(), Error> {
+        self.action("confirm-email-change", encode(confirm)?).await
+    }
+
+    async fn action(&self, action: &str, body: Value) -> Result<(), Error> {
+        let request = self
+            .pocketbase
+            .post(format!("/api/collections/{}/{action}", self.identifier))?
+            .json(&body);
+        let res = self.pocketbase.send(request).await?;
+
+        if !res.status().is_success() {
+            return Err(Error::from_response(res).await);
+        }
+        Ok(())
+    }
+
+    async fn authenticate(
+        &self,
+        action: &str,
+        body: Value,
+    ) -> Result<(AuthorizedClient, Option<Value>), Error> {
+        let request = self
+            .pocketbase
+            .post(format!("/api/collections/{}/{action}", self.identifier))?
+            .json(&body);
+        let result = AuthResult::from_response(self.pocketbase.send(request).await?).await?;
 
         match result {
-            AuthResult::Error { message, data, .. } => {
+            AuthResult::Error { status, message, data } => {
                 Err(Error::Authorization {
+                    status,
                     message: message
                         .clone()
                         .unwrap_or("failed to authenticate user".into()),
                     data,
                 })
             }
-            AuthResult::Success { token, record } => {
-                let claims = unsafe { Claims::decode_unsafe(&token)? };
-                Ok(AuthorizedClient::new(
+            AuthResult::Mfa { mfa_id } => Err(Error::MfaRequired { mfa_id }),
+            AuthResult::Success { token, record, meta } => Ok((
+                AuthorizedClient::try_new(
                     self.pocketbase.base_uri(),
-                    Token {
-                        user: record.as_object().unwrap().get("id").unwrap().as_str().unwrap().to_string(),
-                        collection: self.identifier.to_string(),
-
-                        auth: token.clone(),
-                        refreshable: claims.refreshable,
-                        ty: claims.ty,
-                        expires: Utc.timestamp_opt(claims.exp, 0).unwrap(),
-                    }
-                ))
-            }
+                    Token::from_auth(&self.identifier, token, record)?,
+                )?,
+                meta,
+            )),
+        }
+    }
+
+    pub fn query(self) -> Query<'c, P, N> {
+        Query {
+            collection: self,
+            options: Default::default(),
         }
     }
 
@@ -70,18 +229,47 @@ where
         self,
         options: ListOptions,
     ) -> Result<Paginated<T>, Error> {
-        let res = self
-            .pocketbase
-            .get(format!("/api/collections/{}/records", self.identifier))
-            .query(&options)
-            .send()
-            .await?;
+        let path = format!("/api/collections/{}/records", self.identifier);
+        fetch_list(self.pocketbase, &path, &options).await
+    }
 
-        if !res.status().is_success() {
-            return Err(res.json::<PocketBaseError>().await?.into());
-        }
+    pub fn stream<T: DeserializeOwned>(
+        self,
+        options: ListOptions,
+    ) -> impl Stream<Item = Result<T, Error>> {
+        let pocketbase = self.pocketbase;
+        let path = format!("/api/collections/{}/records", self.identifier);
+        query::paginate(options, DEFAULT_PER_PAGE, move |options| {
+            let path = path.clone();
+            async move { fetch_list(pocketbase, &path, &options).await }
+        })
+    }
 
-        Ok(res.json::<Paginated<T>>().await?)
+    pub async fn get_full_list<T: DeserializeOwned>(
+        self,
+        batch_size: usize,
+        mut options: ListOptions,
+    ) -> Result<Vec<T>, Error> {
+        options.per_page = Some(batch_size);
+        self.stream(options).try_collect().await
+    }
+
+    pub async fn get_first<T: DeserializeOwned>(
+        self,
+        filter: impl std::fmt::Display,
+        mut options: ListOptions,
+    ) -> Result<T, Error> {
+        options.filter = Some(filter.to_string());
+        options.page = Some(1);
+        options.per_page = Some(1);
+        options.skip_total = Some(true);
+
+        let path = format!("/api/collections/{}/records", self.identifier);
+        fetch_list::<_, T>(self.pocketbase, &path, &options)
+            .await?
+            .items
+            .and_then(|items| items.into_iter().next())
+            .ok_or_else(Error::not_found)
     }
 
     pub async fn get_one<T: DeserializeOwned>(
@@ -89,17 +277,16 @@ where
         id: impl std::fmt::Display,
         options: ViewOptions,
     ) -> Result<T, Error> {
-        let res = self
+        let request = self
             .pocketbase
-            .get(format!("/api/collections/{}/records/{id}", self.identifier))
-            .query(&options)
-            .send()
-            .await?;
+            .get(format!("/api/collections/{}/records/{id}", self.identifier))?
+            .query(&options);
+        let res = self.pocketbase.send(request).await?;
 
         if !res.status().is_success() {
-            return Err(res.json::<PocketBaseError>().await?.into());
+            return Err(Error::from_response(res).await);
         }
-        Ok(res.json::<T>().await?)
+        decode::<T>(res).await
     }
 
     pub async fn create<R: DeserializeOwned>(
@@ -108,65 +295,28 @@ where
         files: impl IntoIterator<Item = (String, File)>,
         options: CreateOptions,
     ) -> Result<R, Error> {
-        let mut form = Form::new();
-
-        let record = serde_json::to_value(record)?;
-        let fields = record.as_object().ok_or(Error::Custom(
-            "expected record to be a mapping of fields to values".to_string(),
-        ))?;
-
-        for (name, value) in fields {
-            let text = match value {
-                Value::Null => continue,
-                Value::Bool(v) => v.to_string(),
-                Value::Number(v) => v.to_string(),
-                Value::String(v) => v.to_string(),
-                Value::Array(v) => serde_json::to_string(v)?,
-                Value::Object(v) => serde_json::to_string(v)?,
-            };
-            form = form.text(name.to_string(), text);
-        }
-
-        for (name, file) in files.into_iter() {
-            match file {
-                File::Path(path) => {
-                    let file = tokio::fs::File::open(&path).await?;
-                    let stream = FramedRead::new(file, BytesCodec::new());
-
-                    form = form
-                        .part(
-                            name,
-                            Part::stream(Body::wrap_stream(stream))
-                                .file_name(path.file_name().unwrap().to_string_lossy().to_string())
-                                .mime_str(mime_to_ext::ext_to_mime(path.extension().unwrap().to_string_lossy().as_ref()).unwrap())?
-                        );
-                },
-                File::Raw {
-                    filename,
-                    mime,
-                    bytes,
-                } => form = form
-                    .part(
-                        name,
-                        Part::bytes(bytes)
-                            .file_name(filename)
-                            .mime_str(&mime)?
-                    ),
-            }
+        let record = encode(record)?;
+        if !record.is_object() {
+            return Err(Error::custom("expected record to be a mapping of fields to values"));
         }
+        let files = files.into_iter().collect::<Vec<_>>();
 
-        let res = self
+        let request = self
             .pocketbase
-            .post(format!("/api/collections/{}/records", self.identifier))
-            .query(&options)
-            .multipart(form)
-            .send()
-            .await?;
+            .post(format!("/api/collections/{}/records", self.identifier))?
+            .query(&options);
+        let request = files::body(
+            request,
+            &record,
+            files.iter().map(|(name, file)| (name.clone(), file)),
+        )
+        .await?;
+        let res = self.pocketbase.send(request).await?;
 
         if !res.status().is_success() {
-            return Err(res.json::<PocketBaseError>().await?.into());
+            return Err(Error::from_response(res).await);
         }
-        Ok(res.json::<R>().await?)
+        decode::<R>(res).await
     }
 
     pub async fn update<R: DeserializeOwned>(
@@ -176,77 +326,66 @@ where
         files: impl IntoIterator<Item = (String, File)>,
         options: UpdateOptions,
     ) -> Result<R, Error> {
-        let mut form = Form::new();
-
-        let record = serde_json::to_value(record)?;
-        let fields = record.as_object().ok_or(Error::Custom(
-            "expected record to be a mapping of fields to values".to_string(),
-        ))?;
-
-        for (name, value) in fields {
-            let text = match value {
-                Value::Null => continue,
-                Value::Bool(v) => v.to_string(),
-                Value::Number(v) => v.to_string(),
-                Value::String(v) => v.to_string(),
-                Value::Array(v) => serde_json::to_string(v)?,
-                Value::Object(v) => serde_json::to_string(v)?,
-            };
-            form = form.text(name.to_string(), text);
-        }
-
-        for (name, file) in files.into_iter() {
-            match file {
-                File::Path(path) => {
-                    let file = tokio::fs::File::open(&path).await?;
-                    let stream = FramedRead::new(file, BytesCodec::new());
-
-                    form = form
-                        .part(
-                            name,
-                            Part::stream(Body::wrap_stream(stream))
-                                .file_name(path.file_name().unwrap().to_string_lossy().to_string())
-                                .mime_str(mime_to_ext::ext_to_mime(path.extension().unwrap().to_string_lossy().as_ref()).unwrap())?
-                        );
-                },
-                File::Raw {
-                    filename,
-                    mime,
-                    bytes,
-                } => form = form
-                    .part(
-                        name,
-                        Part::bytes(bytes)
-                            .file_name(filename)
-                            .mime_str(&mime)?
-                    ),
-            }
+        let record = encode(record)?;
+        if !record.is_object() {
+            return Err(Error::custom("expected record to be a mapping of fields to values"));
         }
+        let files = files.into_iter().collect::<Vec<_>>();
 
-        let res = self
+        let request = self
             .pocketbase
-            .patch(format!("/api/collections/{}/records/{id}", self.identifier))
-            .query(&options)
-            .multipart(form)
-            .send()
-            .await?;
+            .patch(format!("/api/collections/{}/records/{id}", self.identifier))?
+            .query(&options);
+        let request = files::body(
+            request,
+            &record,
+            files.iter().map(|(name, file)| (name.clone(), file)),
+        )
+        .await?;
+        let res = self.pocketbase.send(request).await?;
 
         if !res.status().is_success() {
-            return Err(res.json::<PocketBaseError>().await?.into());
+            return Err(Error::from_response(res).await);
         }
-        Ok(res.json::<R>().await?)
+        decode::<R>(res).await
     }
 
     pub async fn delete(self, id: impl std::fmt::Display) -> Result<(), Error> {
-        let res = self
+        let request = self
             .pocketbase
-            .delete(format!("/api/collections/{}/records/{id}", self.identifier))
-            .send()
-            .await?;
+            .delete(format!("/api/collections/{}/records/{id}", self.identifier))?;
+        let res = self.pocketbase.send(request).await?;
 
         if !res.status().is_success() {
-            return Err(res.json::<PocketBaseError>().await?.into());
+            return Err(Error::from_response(res).await);
         }
         Ok(())
     }
+
+    pub async fn subscribe<T: DeserializeOwned + Send + 'static>(
+        self,
+        topic: impl std::fmt::Display,
+    ) -> Result<Subscription<T>, Error> {
+        let topic = format!("{}/{topic}", self.identifier);
+
+        let (client_id, events) = realtime::connect(self.pocketbase).await?;
+        realtime::submit(self.pocketbase, &client_id, [&topic]).await?;
+
+        Ok(Subscription::new(topic, events))
+    }
+}
+
+async fn fetch_list<P: PocketBaseClient, T: DeserializeOwned>(
+    pocketbase: &P,
+    path: &str,
+    options: &ListOptions,
+) -> Result<Paginated<T>, Error> {
+    let request = pocketbase.get(path)?.query(options);
+    let res = pocketbase.send(request).await?;
+
+    if !res.status().is_success() {
+        return Err(Error::from_response(res).await);
+    }
+
+    decode::<Paginated<T>>(res).await
 }
@@ -0,0 +1,326 @@
+use std::{collections::BTreeMap, path::Path};
+
+use chrono::{DateTime, Utc};
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::{Error, ListOptions, error::{decode, encode}, Paginated, client::PocketBaseClient, query, record::datetime};
+
+const FULL_LIST_PER_PAGE: usize = 200;
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, strum::Display,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum CollectionType {
+    #[default]
+    Base,
+    Auth,
+    View,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, strum::Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum FieldType {
+    Text,
+    Number,
+    Bool,
+    Email,
+    Url,
+    Editor,
+    Date,
+    Autodate,
+    Select,
+    File,
+    Relation,
+    Json,
+    Password,
+    Geopoint,
+    /// A field type added by a newer server or a plugin
+    #[serde(untagged)]
+    #[strum(to_string = "{0}")]
+    Other(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CollectionField {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: FieldType,
+    #[serde(default)]
+    pub system: bool,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub presentable: bool,
+    /// Type specific settings such as `required`, `max`, `collectionId` or `values`
+    #[serde(flatten)]
+    pub options: Map<String, Value>,
+}
+
+impl CollectionField {
+    pub fn new(name: impl std::fmt::Display, ty: FieldType) -> Self {
+        Self {
+            id: String::new(),
+            name: name.to_string(),
+            ty,
+            system: false,
+            hidden: false,
+            presentable: false,
+            options: Map::new(),
+        }
+    }
+
+    pub fn required(self, required: bool) -> Self {
+        self.option("required", required)
+    }
+
+    pub fn option(mut self, key: impl std::fmt::Display, value: impl Into<Value>) -> Self {
+        self.options.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordAuthOptions {
+    pub enabled: bool,
+    #[serde(default)]
+    pub identity_fields: Vec<String>,
+}
+
+/// Settings specific to `auth` collections.
+///
+/// Everything that isn't modelled here (oauth2, mfa, otp, token and template settings) is kept in
+/// `extra` so it survives a round trip.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthOptions {
+    #[serde(default)]
+    pub auth_rule: Option<String>,
+    #[serde(default)]
+    pub manage_rule: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_auth: Option<PasswordAuthOptions>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionModel {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type", default)]
+    pub ty: CollectionType,
+    #[serde(default)]
+    pub system: bool,
+    #[serde(default)]
+    pub fields: Vec<CollectionField>,
+    #[serde(default)]
+    pub indexes: Vec<String>,
+    #[serde(default)]
+    pub list_rule: Option<String>,
+    #[serde(default)]
+    pub view_rule: Option<String>,
+    #[serde(default)]
+    pub create_rule: Option<String>,
+    #[serde(default)]
+    pub update_rule: Option<String>,
+    #[serde(default)]
+    pub delete_rule: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view_query: Option<String>,
+    #[serde(default, with = "datetime::option", skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Utc>>,
+    #[serde(default, with = "datetime::option", skip_serializing_if = "Option::is_none")]
+    pub updated: Option<DateTime<Utc>>,
+    /// Type specific settings, e.g. the [`AuthOptions`] of an auth collection
+    #[serde(flatten)]
+    pub options: Map<String, Value>,
+}
+
+impl CollectionModel {
+    pub fn new(name: impl std::fmt::Display, ty: CollectionType) -> Self {
+        Self {
+            name: name.to_string(),
+            ty,
+            ..Default::default()
+        }
+    }
+
+    pub fn base(name: impl std::fmt::Display) -> Self {
+        Self::new(name, CollectionType::Base)
+    }
+
+    pub fn auth(name: impl std::fmt::Display) -> Self {
+        Self::new(name, CollectionType::Auth)
+    }
+
+    pub fn view(name: impl std::fmt::Display, query: impl std::fmt::Display) -> Self {
+        Self {
+            view_query: Some(query.to_string()),
+            ..Self::new(name, CollectionType::View)
+        }
+    }
+
+    pub fn field(mut self, field: CollectionField) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    pub fn index(mut self, index: impl std::fmt::Display) -> Self {
+        self.indexes.push(index.to_string());
+        self
+    }
+
+    pub fn get_field(&self, name: &str) -> Option<&CollectionField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// The auth settings of the collection, or `None` if it isn't an auth collection.
+    pub fn auth_options(&self) -> Result<Option<AuthOptions>, Error> {
+        if self.ty != CollectionType::Auth {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_value(Value::Object(self.options.clone()))?))
+    }
+
+    pub fn set_auth_options(&mut self, options: AuthOptions) -> Result<(), Error> {
+        let Value::Object(options) = encode(options)? else {
+            return Err(Error::custom("expected auth options to be a mapping"));
+        };
+        self.options.extend(options);
+        Ok(())
+    }
+}
+
+pub struct CollectionsBuilder<'c, P: PocketBaseClient> {
+    pub(crate) pocketbase: &'c P,
+}
+
+impl<'c, P: PocketBaseClient> CollectionsBuilder<'c, P> {
+    pub async fn list(&self, options: ListOptions) -> Result<Paginated<CollectionModel>, Error> {
+        let request = self.pocketbase.get("/api/collections")?.query(&options);
+        let res = self.pocketbase.send(request).await?;
+
+        if !res.status().is_success() {
+            return Err(Error::from_response(res).await);
+        }
+        decode::<Paginated<CollectionModel>>(res).await
+    }
+
+    pub async fn get_full_list(&self, options: ListOptions) -> Result<Vec<CollectionModel>, Error> {
+        let pocketbase = self.pocketbase;
+        query::paginate(options, FULL_LIST_PER_PAGE, move |options| async move {
+            CollectionsBuilder { pocketbase }.list(options).await
+        })
+        .try_collect()
+        .await
+    }
+
+    pub async fn view(&self, id_or_name: impl std::fmt::Display) -> Result<CollectionModel, Error> {
+        let request = self.pocketbase.get(format!("/api/collections/{id_or_name}"))?;
+        let res = self.pocketbase.send(request).await?;
+
+        if !res.status().is_success() {
+            return Err(Error::from_response(res).await);
+        }
+        decode::<CollectionModel>(res).await
+    }
+
+    pub async fn create(&self, collection: &impl Serialize) -> Result<CollectionModel, Error> {
+        let request = self.pocketbase.post("/api/collections")?.json(collection);
+        let res = self.pocketbase.send(request).await?;
+
+        if !res.status().is_success() {
+            return Err(Error::from_response(res).await);
+        }
+        decode::<CollectionModel>(res).await
+    }
+
+    pub async fn update(
+        &self,
+        id_or_name: impl std::fmt::Display,
+        collection: &impl Serialize,
+    ) -> Result<CollectionModel, Error> {
+        let request = self
+            .pocketbase
+            .patch(format!("/api/collections/{id_or_name}"))?
+            .json(collection);
+        let res = self.pocketbase.send(request).await?;
+
+        if !res.status().is_success() {
+            return Err(Error::from_response(res).await);
+        }
+        decode::<CollectionModel>(res).await
+    }
+
+    pub async fn delete(&self, id_or_name: impl std::fmt::Display) -> Result<(), Error> {
+        let request = self.pocketbase.delete(format!("/api/collections/{id_or_name}"))?;
+        let res = self.pocketbase.send(request).await?;
+
+        if !res.status().is_success() {
+            return Err(Error::from_response(res).await);
+        }
+        Ok(())
+    }
+
+    /// Delete every record in the collection while keeping its schema.
+    pub async fn truncate(&self, id_or_name: impl std::fmt::Display) -> Result<(), Error> {
+        let request = self
+            .pocketbase
+            .delete(format!("/api/collections/{id_or_name}/truncate"))?;
+        let res = self.pocketbase.send(request).await?;
+
+        if !res.status().is_success() {
+            return Err(Error::from_response(res).await);
+        }
+        Ok(())
+    }
+
+    /// Replace the server's collections with `collections`.
+    ///
+    /// Collections are matched by id; with `delete_missing` any collection (and its records) not
+    /// in the list is deleted. Validation failures are reported through [`Error::field_errors`].
+    pub async fn import(
+        &self,
+        collections: &[CollectionModel],
+        delete_missing: bool,
+    ) -> Result<(), Error> {
+        let request = self
+            .pocketbase
+            .put("/api/collections/import")?
+            .json(&json!({ "collections": collections, "deleteMissing": delete_missing }));
+        let res = self.pocketbase.send(request).await?;
+
+        if !res.status().is_success() {
+            return Err(Error::from_response(res).await);
+        }
+        Ok(())
+    }
+
+    /// Import a collections export as produced by the dashboard.
+    pub async fn import_file(&self, path: impl AsRef<Path>, delete_missing: bool) -> Result<(), Error> {
+        let bytes = tokio::fs::read(path).await?;
+        let collections = serde_json::from_slice::<Vec<CollectionModel>>(&bytes)?;
+        self.import(&collections, delete_missing).await
+    }
+
+    /// The default model of each collection type, keyed by type.
+    pub async fn scaffolds(&self) -> Result<BTreeMap<String, CollectionModel>, Error> {
+        let request = self.pocketbase.get("/api/collections/meta/scaffolds")?;
+        let res = self.pocketbase.send(request).await?;
+
+        if !res.status().is_success() {
+            return Err(Error::from_response(res).await);
+        }
+        decode::<BTreeMap<String, CollectionModel>>(res).await
+    }
+}
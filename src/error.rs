@@ -1,83 +1,268 @@
 use std::collections::BTreeMap;
 
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize, de::DeserializeOwned};
+use serde_json::Value;
 
 use crate::PocketBaseError;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct FieldError {
     pub code: String,
     pub message: String,
+    pub data: BTreeMap<String, FieldError>,
+}
+
+impl FieldError {
+    fn from_value(value: Value) -> Self {
+        match value {
+            Value::Object(map) => {
+                let mut error = FieldError::default();
+                for (name, value) in map {
+                    match (name.as_str(), value) {
+                        ("code", Value::String(code)) => error.code = code,
+                        ("message", Value::String(message)) => error.message = message,
+                        ("data", Value::Object(data)) => error.data.extend(
+                            data.into_iter()
+                                .map(|(name, value)| (name, FieldError::from_value(value))),
+                        ),
+                        (_, value @ Value::Object(_)) => {
+                            error.data.insert(name, FieldError::from_value(value));
+                        }
+                        _ => {}
+                    }
+                }
+                error
+            }
+            Value::String(message) => FieldError {
+                message,
+                ..Default::default()
+            },
+            _ => FieldError::default(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FieldError {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::from_value(Value::deserialize(deserializer)?))
+    }
 }
 
 #[derive(Debug)]
 pub enum Error {
+    Api {
+        status: u16,
+        message: String,
+        data: BTreeMap<String, FieldError>,
+    },
     Authorization {
+        status: u16,
         message: String,
         data: BTreeMap<String, FieldError>
     },
+    MfaRequired {
+        mfa_id: String,
+    },
     Unauthorized,
+    Url(url::ParseError),
+    Transport(reqwest::Error),
+    Decode(serde_json::Error),
+    Encode(Box<dyn std::error::Error + Send + Sync>),
+    Io(std::io::Error),
+    Jwt(jsonwebtoken::errors::Error),
     Custom(String),
 }
 impl Error {
     pub fn custom(value: impl std::fmt::Display) -> Self {
         Self::Custom(value.to_string())
     }
+
+    pub(crate) fn encode(value: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self::Encode(Box::new(value))
+    }
+
+    pub(crate) fn not_found() -> Self {
+        Self::Api {
+            status: 404,
+            message: "The requested resource wasn't found.".to_string(),
+            data: Default::default(),
+        }
+    }
+
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::Api { status, .. } | Self::Authorization { status, .. } => Some(*status),
+            Self::MfaRequired { .. } | Self::Unauthorized => Some(401),
+            Self::Transport(err) => err.status().map(|status| status.as_u16()),
+            _ => None,
+        }
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            Self::Api { message, .. } | Self::Authorization { message, .. } => Some(message),
+            _ => None,
+        }
+    }
+
+    pub fn field_errors(&self) -> Option<&BTreeMap<String, FieldError>> {
+        match self {
+            Self::Api { data, .. } | Self::Authorization { data, .. } => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn mfa_id(&self) -> Option<&str> {
+        match self {
+            Self::MfaRequired { mfa_id } => Some(mfa_id),
+            _ => None,
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(404)
+    }
+
+    pub fn is_validation(&self) -> bool {
+        self.status() == Some(400) && self.field_errors().is_some_and(|data| !data.is_empty())
+    }
+
+    pub fn is_decode(&self) -> bool {
+        match self {
+            Self::Decode(_) => true,
+            Self::Transport(err) => err.is_decode(),
+            _ => false,
+        }
+    }
+
+    pub(crate) async fn from_response(res: reqwest::Response) -> Self {
+        let status = res.status();
+        let body = match res.bytes().await {
+            Ok(body) => body,
+            Err(err) => return err.into(),
+        };
+
+        match serde_json::from_slice::<PocketBaseError>(&body) {
+            Ok(error) => error.into(),
+            Err(_) => {
+                let body = String::from_utf8_lossy(&body);
+                Self::Api {
+                    status: status.as_u16(),
+                    message: match body.trim() {
+                        "" => status.canonical_reason().unwrap_or_default().to_string(),
+                        body => body.to_string(),
+                    },
+                    data: Default::default(),
+                }
+            }
+        }
+    }
+}
+
+fn write_field_errors(
+    f: &mut std::fmt::Formatter<'_>,
+    data: &BTreeMap<String, FieldError>,
+    depth: usize,
+) -> std::fmt::Result {
+    for (name, FieldError { message, data, .. }) in data {
+        write!(f, "\n{:indent$}{name}: {message}", "", indent = (depth + 1) * 2)?;
+        write_field_errors(f, data, depth + 1)?;
+    }
+    Ok(())
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Authorization { message, data } => {
-                writeln!(f, "{message}")?;
-                write!(f,
-                    "  {}",
-                    data.iter()
-                        .map(|(name, FieldError { code: _, message })| format!("{name}: {message}"))
-                        .collect::<Vec<_>>()
-                        .join("\n  ")
-                )
+            Self::Api { status, message, data } => {
+                write!(f, "[{status}] {message}")?;
+                write_field_errors(f, data, 0)
             },
-            Self::Unauthorized => write!(f, "unauthrized"),
+            Self::Authorization { message, data, .. } => {
+                write!(f, "{message}")?;
+                write_field_errors(f, data, 0)
+            },
+            Self::MfaRequired { mfa_id } => write!(f, "multi-factor authentication required ({mfa_id})"),
+            Self::Unauthorized => write!(f, "unauthorized"),
+            Self::Url(err) => write!(f, "invalid url: {err}"),
+            Self::Transport(err) => write!(f, "request failed: {err}"),
+            Self::Decode(err) => write!(f, "failed to decode json: {err}"),
+            Self::Encode(err) => write!(f, "failed to encode request: {err}"),
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Jwt(err) => write!(f, "invalid token: {err}"),
             Self::Custom(value) => f.write_str(value),
         }
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Url(err) => Some(err),
+            Self::Transport(err) => Some(err),
+            Self::Decode(err) => Some(err),
+            Self::Encode(err) => Some(err.as_ref()),
+            Self::Io(err) => Some(err),
+            Self::Jwt(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<url::ParseError> for Error {
+    fn from(value: url::ParseError) -> Self {
+        Self::Url(value)
+    }
+}
 
 impl From<reqwest::Error> for Error {
     fn from(value: reqwest::Error) -> Self {
-        Self::Custom(value.to_string())
+        Self::Transport(value)
     }
 }
 
 impl From<jsonwebtoken::errors::Error> for Error {
     fn from(value: jsonwebtoken::errors::Error) -> Self {
-        Self::Custom(value.to_string())
+        Self::Jwt(value)
     }
 }
 
 impl From<serde_json::Error> for Error {
     fn from(value: serde_json::Error) -> Self {
-        Self::Custom(value.to_string())
+        Self::Decode(value)
     }
 }
 
 impl From<serde_urlencoded::ser::Error> for Error {
     fn from(value: serde_urlencoded::ser::Error) -> Self {
-        Self::Custom(value.to_string())
+        Self::Encode(Box::new(value))
     }
 }
 
 impl From<std::io::Error> for Error {
     fn from(value: std::io::Error) -> Self {
-        Self::Custom(value.to_string())
+        Self::Io(value)
     }
 }
 
 impl From<PocketBaseError> for Error {
     fn from(value: PocketBaseError) -> Self {
-        Self::Custom(value.to_string())
+        Self::Api {
+            status: value.status,
+            message: value.message,
+            data: value.data,
+        }
     }
-}
\ No newline at end of file
+}
+
+/// Read a JSON response body, reporting malformed bodies as [`Error::Decode`] rather than as a
+/// transport error.
+pub(crate) async fn decode<T: DeserializeOwned>(res: reqwest::Response) -> Result<T, Error> {
+    let body = res.bytes().await?;
+    Ok(serde_json::from_slice::<T>(&body)?)
+}
+
+/// Serialize a request body, reporting failures as [`Error::Encode`].
+pub(crate) fn encode<T: Serialize>(value: T) -> Result<Value, Error> {
+    serde_json::to_value(value).map_err(Error::encode)
+}
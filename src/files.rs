@@ -3,9 +3,16 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use serde::Deserialize;
+use reqwest::{
+    Body, RequestBuilder,
+    multipart::{Form, Part},
+};
+use serde::{Deserialize, Serialize};
+use tokio_util::codec::{BytesCodec, FramedRead};
 use url::Url;
 
+use crate::Error;
+
 pub struct FilesBuilder<'c> {
     pub(crate) base_uri: &'c Url,
 }
@@ -16,10 +23,10 @@ impl<'c> FilesBuilder<'c> {
         collection_id: impl std::fmt::Display,
         id: impl std::fmt::Display,
         filename: impl std::fmt::Display,
-    ) -> Url {
-        self.base_uri
-            .join(&format!("/api/files/{collection_id}/{id}/{filename}"))
-            .unwrap()
+    ) -> Result<Url, Error> {
+        Ok(self
+            .base_uri
+            .join(&format!("/api/files/{collection_id}/{id}/{filename}"))?)
     }
 }
 
@@ -51,16 +58,50 @@ impl File {
         }
     }
 
-    // pub(crate) async fn into_form_part(self) -> Result<Part, Error> {
-    //     Ok(match self {
-    //         Self::Path(path) => Part::file(path).await?,
-    //         Self::Raw { mime, bytes, filename: name } => {
-    //             Part::bytes(bytes)
-    //                 .mime_str(&mime)?
-    //                 .file_name(name)
-    //         }
-    //     })
-    // }
+    pub(crate) async fn to_part(&self) -> Result<Part, Error> {
+        Ok(match self {
+            Self::Path(path) => {
+                let filename = path
+                    .file_name()
+                    .ok_or_else(|| Error::custom(format!("`{}` is not a file", path.display())))?
+                    .to_string_lossy()
+                    .to_string();
+                let mime = path
+                    .extension()
+                    .and_then(|ext| mime_to_ext::ext_to_mime(ext.to_string_lossy().as_ref()))
+                    .unwrap_or("application/octet-stream");
+
+                let file = tokio::fs::File::open(path).await?;
+                let stream = FramedRead::new(file, BytesCodec::new());
+
+                Part::stream(Body::wrap_stream(stream))
+                    .file_name(filename)
+                    .mime_str(mime)?
+            }
+            Self::Raw { filename, mime, bytes } => Part::bytes(bytes.clone())
+                .file_name(filename.clone())
+                .mime_str(mime)?,
+        })
+    }
+}
+
+/// Send `payload` as a JSON body, switching to multipart with an `@jsonPayload` part only when
+/// there are files to upload.
+pub(crate) async fn body<'f>(
+    request: RequestBuilder,
+    payload: &impl Serialize,
+    files: impl IntoIterator<Item = (String, &'f File)>,
+) -> Result<RequestBuilder, Error> {
+    let mut files = files.into_iter().peekable();
+    if files.peek().is_none() {
+        return Ok(request.json(payload));
+    }
+
+    let mut form = Form::new().text("@jsonPayload", serde_json::to_string(payload).map_err(Error::encode)?);
+    for (name, file) in files {
+        form = form.part(name, file.to_part().await?);
+    }
+    Ok(request.multipart(form))
 }
 
 impl From<String> for File {
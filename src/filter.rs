@@ -0,0 +1,392 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::{Error, record::datetime};
+
+/// A literal value in a filter expression.
+///
+/// Strings are quoted and their single quotes escaped. Values are checked when they are bound by
+/// [`Filter::compare`] or [`bind`]: non-finite numbers have no filter syntax, and PocketBase string
+/// literals can't end with a backslash since it would escape the closing quote.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterValue {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    DateTime(DateTime<Utc>),
+}
+
+impl std::fmt::Display for FilterValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => f.write_str("null"),
+            Self::Bool(value) => write!(f, "{value}"),
+            Self::Number(value) => f.write_str(value),
+            Self::String(value) => write!(f, "'{}'", value.replace('\'', "\\'")),
+            Self::DateTime(value) => write!(f, "'{}'", datetime::format(value)),
+        }
+    }
+}
+
+macro_rules! number {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for FilterValue {
+                fn from(value: $ty) -> Self {
+                    Self::Number(value.to_string())
+                }
+            }
+        )*
+    };
+}
+number!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+number!(f32, f64);
+
+impl FilterValue {
+    /// The value as a filter literal, failing for values that can't be expressed safely.
+    pub fn literal(&self) -> Result<String, Error> {
+        match self {
+            Self::Number(value) if !value.parse::<f64>().is_ok_and(f64::is_finite) => Err(
+                Error::custom(format!("`{value}` can't be used in a filter")),
+            ),
+            Self::String(value) if value.ends_with('\\') => Err(Error::custom(format!(
+                "`{value}` can't be used in a filter, string values can't end with a backslash"
+            ))),
+            value => Ok(value.to_string()),
+        }
+    }
+}
+
+impl From<bool> for FilterValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<&str> for FilterValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for FilterValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&String> for FilterValue {
+    fn from(value: &String) -> Self {
+        Self::String(value.clone())
+    }
+}
+
+impl From<DateTime<Utc>> for FilterValue {
+    fn from(value: DateTime<Utc>) -> Self {
+        Self::DateTime(value)
+    }
+}
+
+impl<T: Into<FilterValue>> From<Option<T>> for FilterValue {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Self::Null)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+pub enum Operator {
+    #[strum(serialize = "=")]
+    Eq,
+    #[strum(serialize = "!=")]
+    Ne,
+    #[strum(serialize = ">")]
+    Gt,
+    #[strum(serialize = ">=")]
+    Ge,
+    #[strum(serialize = "<")]
+    Lt,
+    #[strum(serialize = "<=")]
+    Le,
+    #[strum(serialize = "~")]
+    Like,
+    #[strum(serialize = "!~")]
+    NotLike,
+    #[strum(serialize = "?=")]
+    AnyEq,
+    #[strum(serialize = "?!=")]
+    AnyNe,
+    #[strum(serialize = "?>")]
+    AnyGt,
+    #[strum(serialize = "?>=")]
+    AnyGe,
+    #[strum(serialize = "?<")]
+    AnyLt,
+    #[strum(serialize = "?<=")]
+    AnyLe,
+    #[strum(serialize = "?~")]
+    AnyLike,
+    #[strum(serialize = "?!~")]
+    AnyNotLike,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Join {
+    /// A single comparison or an already grouped expression
+    None,
+    /// An arbitrary expression whose precedence is unknown
+    Raw,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    expr: String,
+    join: Join,
+}
+
+impl Filter {
+    /// An expression used verbatim. It is parenthesized when joined with other filters.
+    pub fn raw(expr: impl Into<String>) -> Self {
+        Self {
+            expr: expr.into(),
+            join: Join::Raw,
+        }
+    }
+
+    pub fn compare(
+        field: impl std::fmt::Display,
+        operator: Operator,
+        value: impl Into<FilterValue>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            expr: format!("{field} {operator} {}", value.into().literal()?),
+            join: Join::None,
+        })
+    }
+
+    pub fn eq(field: impl std::fmt::Display, value: impl Into<FilterValue>) -> Result<Self, Error> {
+        Self::compare(field, Operator::Eq, value)
+    }
+
+    pub fn ne(field: impl std::fmt::Display, value: impl Into<FilterValue>) -> Result<Self, Error> {
+        Self::compare(field, Operator::Ne, value)
+    }
+
+    pub fn gt(field: impl std::fmt::Display, value: impl Into<FilterValue>) -> Result<Self, Error> {
+        Self::compare(field, Operator::Gt, value)
+    }
+
+    pub fn ge(field: impl std::fmt::Display, value: impl Into<FilterValue>) -> Result<Self, Error> {
+        Self::compare(field, Operator::Ge, value)
+    }
+
+    pub fn lt(field: impl std::fmt::Display, value: impl Into<FilterValue>) -> Result<Self, Error> {
+        Self::compare(field, Operator::Lt, value)
+    }
+
+    pub fn le(field: impl std::fmt::Display, value: impl Into<FilterValue>) -> Result<Self, Error> {
+        Self::compare(field, Operator::Le, value)
+    }
+
+    pub fn like(field: impl std::fmt::Display, value: impl Into<FilterValue>) -> Result<Self, Error> {
+        Self::compare(field, Operator::Like, value)
+    }
+
+    pub fn not_like(field: impl std::fmt::Display, value: impl Into<FilterValue>) -> Result<Self, Error> {
+        Self::compare(field, Operator::NotLike, value)
+    }
+
+    pub fn any_eq(field: impl std::fmt::Display, value: impl Into<FilterValue>) -> Result<Self, Error> {
+        Self::compare(field, Operator::AnyEq, value)
+    }
+
+    pub fn any_ne(field: impl std::fmt::Display, value: impl Into<FilterValue>) -> Result<Self, Error> {
+        Self::compare(field, Operator::AnyNe, value)
+    }
+
+    pub fn any_like(field: impl std::fmt::Display, value: impl Into<FilterValue>) -> Result<Self, Error> {
+        Self::compare(field, Operator::AnyLike, value)
+    }
+
+    pub fn and(self, other: Filter) -> Self {
+        self.join(other, Join::And, "&&")
+    }
+
+    pub fn or(self, other: Filter) -> Self {
+        self.join(other, Join::Or, "||")
+    }
+
+    pub fn group(self) -> Self {
+        Self {
+            expr: format!("({})", self.expr),
+            join: Join::None,
+        }
+    }
+
+    fn join(self, other: Filter, join: Join, operator: &str) -> Self {
+        let operand = |filter: Filter| match filter.join {
+            Join::None => filter.expr,
+            current if current == join => filter.expr,
+            _ => filter.group().expr,
+        };
+
+        Self {
+            expr: format!("{} {operator} {}", operand(self), operand(other)),
+            join,
+        }
+    }
+}
+
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+impl From<Filter> for String {
+    fn from(value: Filter) -> Self {
+        value.expr
+    }
+}
+
+/// Substitute `{:name}` placeholders with the matching parameter. Unknown placeholders are kept.
+pub fn bind<'a>(
+    expr: &str,
+    params: impl IntoIterator<Item = (&'a str, FilterValue)>,
+) -> Result<String, Error> {
+    let params = params.into_iter().collect::<BTreeMap<_, _>>();
+
+    let mut result = String::with_capacity(expr.len());
+    let mut rest = expr;
+    while let Some(start) = rest.find("{:") {
+        result.push_str(&rest[..start]);
+        let placeholder = &rest[start..];
+        match placeholder
+            .find('}')
+            .and_then(|end| params.get(&placeholder[2..end]).map(|value| (end, value)))
+        {
+            Some((end, value)) => {
+                result.push_str(&value.literal()?);
+                rest = &placeholder[end + 1..];
+            }
+            None => {
+                result.push_str("{:");
+                rest = &placeholder[2..];
+            }
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[macro_export]
+macro_rules! filter {
+    ($expr:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::filter::bind(
+            $expr,
+            [$((stringify!($name), $crate::filter::FilterValue::from($value))),*],
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn escapes_string_values() {
+        assert_eq!(FilterValue::from("it's").literal().unwrap(), r"'it\'s'");
+        assert_eq!(
+            FilterValue::from("' || 1=1 || '").literal().unwrap(),
+            r"'\' || 1=1 || \''"
+        );
+        assert_eq!(FilterValue::from(r"a\b").literal().unwrap(), r"'a\b'");
+    }
+
+    #[test]
+    fn rejects_trailing_backslash() {
+        assert!(FilterValue::from(r"admin\").literal().is_err());
+        assert!(Filter::eq("name", r"admin\").is_err());
+    }
+
+    #[test]
+    fn formats_literals() {
+        assert_eq!(FilterValue::from(None::<i32>).literal().unwrap(), "null");
+        assert_eq!(FilterValue::from(true).literal().unwrap(), "true");
+        assert_eq!(FilterValue::from(-3).literal().unwrap(), "-3");
+        assert_eq!(FilterValue::from(1.5).literal().unwrap(), "1.5");
+        assert_eq!(
+            FilterValue::from(Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap())
+                .literal()
+                .unwrap(),
+            "'2024-01-02 03:04:05.000Z'"
+        );
+    }
+
+    #[test]
+    fn compares_floats() {
+        assert_eq!(Filter::gt("price", 9.99).unwrap().to_string(), "price > 9.99");
+        assert_eq!(Filter::le("ratio", 0.5f32).unwrap().to_string(), "ratio <= 0.5");
+    }
+
+    #[test]
+    fn rejects_non_finite_floats() {
+        assert!(FilterValue::from(f64::NAN).literal().is_err());
+        assert!(Filter::gt("price", f64::INFINITY).is_err());
+        assert!(crate::filter!("price > {:p}", p = f32::NEG_INFINITY).is_err());
+    }
+
+    #[test]
+    fn groups_mixed_joins() {
+        let filter = Filter::eq("a", 1)
+            .unwrap()
+            .or(Filter::eq("b", 2).unwrap())
+            .and(Filter::eq("c", "x").unwrap());
+        assert_eq!(filter.to_string(), "(a = 1 || b = 2) && c = 'x'");
+
+        let filter = Filter::eq("a", 1)
+            .unwrap()
+            .and(Filter::eq("b", 2).unwrap())
+            .and(Filter::eq("c", 3).unwrap());
+        assert_eq!(filter.to_string(), "a = 1 && b = 2 && c = 3");
+    }
+
+    #[test]
+    fn groups_raw_operands() {
+        let filter = Filter::raw("a = 1 || b = 2").and(Filter::eq("c", "x").unwrap());
+        assert_eq!(filter.to_string(), "(a = 1 || b = 2) && c = 'x'");
+
+        let filter = Filter::eq("c", "x").unwrap().or(Filter::raw("a = 1 && b = 2"));
+        assert_eq!(filter.to_string(), "c = 'x' || (a = 1 && b = 2)");
+    }
+
+    #[test]
+    fn binds_placeholders() {
+        let expr = crate::filter!(
+            "title ~ {:title} && views > {:views} && price > {:price} && missing = {:missing}",
+            title = "it's",
+            views = 10,
+            price = 9.99,
+        );
+        assert_eq!(
+            expr.unwrap(),
+            r"title ~ 'it\'s' && views > 10 && price > 9.99 && missing = {:missing}"
+        );
+        assert!(crate::filter!("name = {:n}", n = r"admin\").is_err());
+    }
+
+    #[test]
+    fn does_not_rebind_substituted_values() {
+        let expr = bind(
+            "a = {:a} && b = {:b}",
+            [("a", FilterValue::from("{:b}")), ("b", FilterValue::from(1))],
+        );
+        assert_eq!(expr.unwrap(), "a = '{:b}' && b = 1");
+    }
+}
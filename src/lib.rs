@@ -1,15 +1,41 @@
 use std::collections::BTreeMap;
 
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 
 pub type Record = serde_json::Map<String, Value>;
 
+pub mod auth;
 pub mod batch;
 pub mod collection;
+pub mod collections;
+pub use collections::{
+    AuthOptions, CollectionField, CollectionModel, CollectionType, CollectionsBuilder, FieldType,
+};
 
 mod error;
-pub use error::Error;
+pub use error::{Error, FieldError};
+
+pub mod filter;
+pub use filter::{Filter, FilterValue};
+
+pub mod query;
+pub use query::{Expand, Fields, Query, Sort, SortBy};
+
+pub mod migrate;
+pub use migrate::{Plan, Schema};
+
+pub mod patch;
+pub use patch::Patch;
+
+pub mod record;
+pub use record::{BaseRecord, PocketRecord, RecordMeta, TypedRecord};
+#[cfg(feature = "derive")]
+pub use pocket_derive::PocketRecord;
+
+pub mod realtime;
+pub use realtime::{Action, Backoff, Realtime, RealtimeEvent, RealtimeMessage, Subscription};
 
 pub mod files;
 pub use files::FilesBuilder;
@@ -17,44 +43,59 @@ pub use files::FilesBuilder;
 pub mod client;
 pub use client::{Client, AuthorizedClient, Token, PocketBaseClient};
 
+pub mod store;
+pub use store::{AuthStore, FileStore, MemoryStore};
+
 use crate::files::File;
 
 #[derive(Debug, Deserialize)]
 struct PocketBaseError {
     status: u16,
     message: String,
-    data: Value,
+    #[serde(default)]
+    data: BTreeMap<String, FieldError>,
 }
-impl std::fmt::Display for PocketBaseError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "[{}] {}: {}",
-            self.status,
-            self.message,
-            serde_json::to_string_pretty(&self.data).unwrap_or(String::new())
-        )
-    }
-}
-impl std::error::Error for PocketBaseError {}
 
-#[allow(dead_code)]
-#[derive(Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct Claims {
+pub struct Claims {
     pub id: String,
+    #[serde(default)]
     pub collection_id: String,
     pub exp: i64,
+    #[serde(default)]
     pub refreshable: bool,
     #[serde(rename = "type")]
     pub ty: String,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
 }
 
 impl Claims {
+    /// # Safety
+    ///
+    /// The signature is not checked, so the claims must not be trusted for
+    /// authorization decisions. Use [`Claims::decode`] when the secret is known.
     pub unsafe fn decode_unsafe(token: &str) -> Result<Claims, Error> {
         let token = jsonwebtoken::dangerous::insecure_decode::<Claims>(token)?;
         Ok(token.claims)
     }
+
+    /// Decode and validate the signature and expiry of `token`.
+    ///
+    /// PocketBase signs auth tokens with the record's `tokenKey` followed by the
+    /// collection's token secret, so `secret` is the concatenation of both.
+    pub fn decode(token: &str, secret: impl AsRef<[u8]>) -> Result<Claims, Error> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_required_spec_claims(&["exp"]);
+
+        let token = jsonwebtoken::decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(secret.as_ref()),
+            &validation,
+        )?;
+        Ok(token.claims)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -75,8 +116,8 @@ impl Health {
 pub struct Paginated<T> {
     pub page: usize,
     pub per_page: usize,
-    pub total_items: usize,
-    pub total_pages: usize,
+    pub total_items: i64,
+    pub total_pages: i64,
     pub items: Option<Vec<T>>,
 }
 
@@ -0,0 +1,712 @@
+use std::{collections::BTreeMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    CollectionField, CollectionModel, CollectionType, CollectionsBuilder, Error, FieldType,
+    ListOptions, client::PocketBaseClient,
+};
+
+/// The desired collections of a PocketBase instance.
+///
+/// Relation fields may reference their target by name in `collectionId`; names are resolved to ids
+/// when planning.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+    #[serde(default)]
+    pub collections: Vec<CollectionModel>,
+    /// Delete collections on the server that aren't part of the schema. System collections are
+    /// never deleted.
+    #[serde(default)]
+    pub delete_missing: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SchemaFile {
+    Schema(Schema),
+    Export(Vec<CollectionModel>),
+}
+
+impl From<SchemaFile> for Schema {
+    fn from(value: SchemaFile) -> Self {
+        match value {
+            SchemaFile::Schema(schema) => schema,
+            SchemaFile::Export(collections) => Schema {
+                collections,
+                delete_missing: false,
+            },
+        }
+    }
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn collection(mut self, collection: CollectionModel) -> Self {
+        self.collections.push(collection);
+        self
+    }
+
+    pub fn delete_missing(mut self, delete_missing: bool) -> Self {
+        self.delete_missing = delete_missing;
+        self
+    }
+
+    /// Parse a schema, or a collections export as produced by the dashboard.
+    pub fn from_json(value: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str::<SchemaFile>(value)?.into())
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn from_toml(value: &str) -> Result<Self, Error> {
+        toml::from_str::<Schema>(value).map_err(Error::custom)
+    }
+
+    /// Load a schema from a `.json` or, with the `toml` feature, a `.toml` file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let value = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            #[cfg(feature = "toml")]
+            Some("toml") => Self::from_toml(&value),
+            #[cfg(not(feature = "toml"))]
+            Some("toml") => Err(Error::custom(format!(
+                "`{}` requires the `toml` feature",
+                path.display()
+            ))),
+            _ => Self::from_json(&value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CollectionChange {
+    AddField(CollectionField),
+    RemoveField(CollectionField),
+    ChangeField {
+        from: CollectionField,
+        to: CollectionField,
+    },
+    /// PocketBase can't change the type of an existing field, so it is removed and added again,
+    /// dropping its values
+    ReplaceField {
+        from: CollectionField,
+        to: CollectionField,
+    },
+    Rule {
+        rule: &'static str,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    ViewQuery {
+        from: Option<String>,
+        to: String,
+    },
+    Option {
+        key: String,
+        from: Option<Value>,
+        to: Value,
+    },
+    AddIndex(String),
+    RemoveIndex(String),
+}
+
+impl CollectionChange {
+    /// Whether applying the change deletes existing data.
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, Self::RemoveField(_) | Self::ReplaceField { .. })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Create(CollectionModel),
+    Update {
+        /// The collection as it should be after the update
+        collection: CollectionModel,
+        changes: Vec<CollectionChange>,
+    },
+    Delete(CollectionModel),
+}
+
+impl Change {
+    /// Whether applying the change deletes existing data.
+    pub fn is_destructive(&self) -> bool {
+        match self {
+            Self::Create(_) => false,
+            Self::Update { changes, .. } => changes.iter().any(CollectionChange::is_destructive),
+            Self::Delete(_) => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Plan {
+    pub changes: Vec<Change>,
+    /// The server's collections the plan was computed against
+    base: Vec<CollectionModel>,
+}
+
+impl Plan {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn is_destructive(&self) -> bool {
+        self.changes.iter().any(Change::is_destructive)
+    }
+
+    /// The complete set of collections once the plan is applied.
+    fn collections(&self) -> Vec<CollectionModel> {
+        let mut collections = self
+            .base
+            .iter()
+            .filter_map(|existing| {
+                let mut collection = Some(existing.clone());
+                for change in &self.changes {
+                    match change {
+                        Change::Update {
+                            collection: target, ..
+                        } if target.id == existing.id => {
+                            collection = Some(target.clone());
+                        }
+                        Change::Delete(deleted) if deleted.id == existing.id => collection = None,
+                        _ => {}
+                    }
+                }
+                collection
+            })
+            .collect::<Vec<_>>();
+
+        for change in &self.changes {
+            if let Change::Create(collection) = change {
+                collections.push(collection.clone());
+            }
+        }
+        collections
+    }
+}
+
+fn rule(value: &Option<String>) -> String {
+    match value {
+        Some(value) => format!("{value:?}"),
+        None => "null".to_string(),
+    }
+}
+
+impl std::fmt::Display for CollectionChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AddField(field) => write!(f, "+ field `{}` ({})", field.name, field.ty),
+            Self::RemoveField(field) => write!(
+                f,
+                "- field `{}` ({}), its values are deleted",
+                field.name, field.ty
+            ),
+            Self::ChangeField { to, .. } => write!(f, "~ field `{}` ({})", to.name, to.ty),
+            Self::ReplaceField { from, to } => write!(
+                f,
+                "! field `{}` is recreated ({} -> {}), its values are deleted",
+                to.name, from.ty, to.ty
+            ),
+            Self::Rule {
+                rule: name,
+                from,
+                to,
+            } => {
+                write!(f, "~ {name}: {} -> {}", rule(from), rule(to))
+            }
+            Self::ViewQuery { from, to } => write!(f, "~ viewQuery: {} -> {to:?}", rule(from)),
+            Self::Option {
+                key,
+                from: Some(from),
+                to,
+            } => write!(f, "~ {key}: {from} -> {to}"),
+            Self::Option {
+                key,
+                from: None,
+                to,
+            } => write!(f, "+ {key}: {to}"),
+            Self::AddIndex(index) => write!(f, "+ index `{index}`"),
+            Self::RemoveIndex(index) => write!(f, "- index `{index}`"),
+        }
+    }
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Create(collection) => write!(
+                f,
+                "+ create collection `{}` ({}, {} fields)",
+                collection.name,
+                collection.ty,
+                collection.fields.len()
+            ),
+            Self::Delete(collection) => write!(
+                f,
+                "- delete collection `{}` and all of its records",
+                collection.name
+            ),
+            Self::Update {
+                collection,
+                changes,
+            } => {
+                write!(f, "~ update collection `{}`", collection.name)?;
+                for change in changes {
+                    write!(f, "\n    {change}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Plan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.changes.is_empty() {
+            return f.write_str("no changes");
+        }
+        for (i, change) in self.changes.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A deterministic id for a new collection, so relations can point at it before it exists.
+fn collection_id(name: &str) -> String {
+    let mut crc = !0u32;
+    for byte in name.bytes() {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    format!("pbc_{}", !crc)
+}
+
+/// Replace relation targets given by collection name with the collection's id.
+fn resolve(field: &CollectionField, ids: &BTreeMap<String, String>) -> CollectionField {
+    let mut field = field.clone();
+    if field.ty == FieldType::Relation
+        && let Some(Value::String(target)) = field.options.get("collectionId")
+        && let Some(id) = ids.get(target)
+    {
+        field
+            .options
+            .insert("collectionId".to_string(), Value::String(id.clone()));
+    }
+    field
+}
+
+/// Auth collections always carry unique indexes on `tokenKey` and `email` that the server manages.
+fn is_managed_index(collection: &CollectionModel, index: &str) -> bool {
+    collection.ty == CollectionType::Auth
+        && (index.contains("idx_tokenKey_") || index.contains("idx_email_"))
+}
+
+fn field_changed(local: &CollectionField, remote: &CollectionField) -> bool {
+    local.hidden != remote.hidden
+        || local.presentable != remote.presentable
+        || local
+            .options
+            .iter()
+            .any(|(key, value)| remote.options.get(key) != Some(value))
+}
+
+fn diff(
+    local: &CollectionModel,
+    remote: &CollectionModel,
+    ids: &BTreeMap<String, String>,
+) -> Result<(CollectionModel, Vec<CollectionChange>), Error> {
+    if local.ty != remote.ty {
+        return Err(Error::custom(format!(
+            "collection `{}` can't change type from {} to {}; create a new collection instead",
+            local.name, remote.ty, local.ty
+        )));
+    }
+
+    let mut target = remote.clone();
+    let mut changes = Vec::new();
+
+    let fields = local
+        .fields
+        .iter()
+        .map(|field| resolve(field, ids))
+        .collect::<Vec<_>>();
+    target.fields.clear();
+    for existing in &remote.fields {
+        match fields.iter().find(|field| field.name == existing.name) {
+            Some(field) if field.ty != existing.ty => {
+                let mut replacement = field.clone();
+                replacement.id.clear();
+                target.fields.push(replacement.clone());
+                changes.push(CollectionChange::ReplaceField {
+                    from: existing.clone(),
+                    to: replacement,
+                });
+            }
+            Some(field) => {
+                let mut merged = existing.clone();
+                if field_changed(field, existing) {
+                    merged.hidden = field.hidden;
+                    merged.presentable = field.presentable;
+                    merged.options.extend(field.options.clone());
+                    changes.push(CollectionChange::ChangeField {
+                        from: existing.clone(),
+                        to: merged.clone(),
+                    });
+                }
+                target.fields.push(merged);
+            }
+            None if existing.system => target.fields.push(existing.clone()),
+            None => changes.push(CollectionChange::RemoveField(existing.clone())),
+        }
+    }
+    for field in &fields {
+        if remote.get_field(&field.name).is_none() {
+            target.fields.push(field.clone());
+            changes.push(CollectionChange::AddField(field.clone()));
+        }
+    }
+
+    let rules = [
+        ("listRule", &local.list_rule, &mut target.list_rule),
+        ("viewRule", &local.view_rule, &mut target.view_rule),
+        ("createRule", &local.create_rule, &mut target.create_rule),
+        ("updateRule", &local.update_rule, &mut target.update_rule),
+        ("deleteRule", &local.delete_rule, &mut target.delete_rule),
+    ];
+    for (name, local, target) in rules {
+        if local != target {
+            changes.push(CollectionChange::Rule {
+                rule: name,
+                from: target.clone(),
+                to: local.clone(),
+            });
+            *target = local.clone();
+        }
+    }
+
+    if let Some(query) = &local.view_query
+        && remote.view_query.as_ref() != Some(query)
+    {
+        changes.push(CollectionChange::ViewQuery {
+            from: remote.view_query.clone(),
+            to: query.clone(),
+        });
+        target.view_query = Some(query.clone());
+    }
+
+    for (key, value) in &local.options {
+        if remote.options.get(key) != Some(value) {
+            changes.push(CollectionChange::Option {
+                key: key.clone(),
+                from: remote.options.get(key).cloned(),
+                to: value.clone(),
+            });
+            target.options.insert(key.clone(), value.clone());
+        }
+    }
+
+    target.indexes.clear();
+    for index in &remote.indexes {
+        if local.indexes.contains(index) || is_managed_index(remote, index) {
+            target.indexes.push(index.clone());
+        } else {
+            changes.push(CollectionChange::RemoveIndex(index.clone()));
+        }
+    }
+    for index in &local.indexes {
+        if !remote.indexes.contains(index) {
+            target.indexes.push(index.clone());
+            changes.push(CollectionChange::AddIndex(index.clone()));
+        }
+    }
+
+    Ok((target, changes))
+}
+
+fn plan(schema: &Schema, remote: Vec<CollectionModel>) -> Result<Plan, Error> {
+    let mut ids = remote
+        .iter()
+        .map(|collection| (collection.name.clone(), collection.id.clone()))
+        .collect::<BTreeMap<_, _>>();
+    for local in &schema.collections {
+        ids.entry(local.name.clone())
+            .or_insert_with(|| match local.id.is_empty() {
+                true => collection_id(&local.name),
+                false => local.id.clone(),
+            });
+    }
+
+    let mut changes = Vec::new();
+    for local in &schema.collections {
+        match remote
+            .iter()
+            .find(|collection| collection.name == local.name)
+        {
+            Some(existing) => {
+                let (collection, diff) = diff(local, existing, &ids)?;
+                if !diff.is_empty() {
+                    changes.push(Change::Update {
+                        collection,
+                        changes: diff,
+                    });
+                }
+            }
+            None => {
+                let mut collection = local.clone();
+                collection.id = ids[&local.name].clone();
+                collection.fields = local
+                    .fields
+                    .iter()
+                    .map(|field| resolve(field, &ids))
+                    .collect();
+                changes.push(Change::Create(collection));
+            }
+        }
+    }
+
+    if schema.delete_missing {
+        for existing in &remote {
+            if !existing.system
+                && !schema
+                    .collections
+                    .iter()
+                    .any(|local| local.name == existing.name)
+            {
+                changes.push(Change::Delete(existing.clone()));
+            }
+        }
+    }
+
+    Ok(Plan {
+        changes,
+        base: remote,
+    })
+}
+
+impl<P: PocketBaseClient> CollectionsBuilder<'_, P> {
+    /// Compute the changes needed to bring the server in line with `schema`.
+    ///
+    /// Fails if a collection would have to change its type, which PocketBase doesn't support.
+    pub async fn plan(&self, schema: &Schema) -> Result<Plan, Error> {
+        plan(schema, self.get_full_list(ListOptions::default()).await?)
+    }
+
+    /// Apply a plan computed by [`plan`](Self::plan) in a single import, so either every change
+    /// is applied or none is.
+    ///
+    /// Fails without changing anything if the server's collections changed since the plan was
+    /// computed.
+    pub async fn apply(&self, plan: &Plan) -> Result<(), Error> {
+        if plan.is_empty() {
+            return Ok(());
+        }
+
+        let current = self.get_full_list(ListOptions::default()).await?;
+        if current != plan.base {
+            return Err(Error::custom(
+                "the collections changed since the plan was computed; plan again",
+            ));
+        }
+
+        let delete_missing = plan
+            .changes
+            .iter()
+            .any(|change| matches!(change, Change::Delete(_)));
+        self.import(&plan.collections(), delete_missing).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn remote(name: &str) -> CollectionModel {
+        let mut collection = CollectionModel::base(name);
+        collection.id = format!("id_{name}");
+        let mut id = CollectionField::new("id", FieldType::Text);
+        id.id = "f_id".to_string();
+        id.system = true;
+        let mut title = CollectionField::new("title", FieldType::Text).required(false);
+        title.id = "f_title".to_string();
+        collection.fields = vec![id, title];
+        collection
+    }
+
+    fn diff_only(local: &CollectionModel, remote: &CollectionModel) -> Vec<CollectionChange> {
+        diff(local, remote, &BTreeMap::new()).unwrap().1
+    }
+
+    #[test]
+    fn field_changes() {
+        let existing = remote("posts");
+        let local = CollectionModel::base("posts")
+            .field(CollectionField::new("title", FieldType::Text).required(true))
+            .field(CollectionField::new("views", FieldType::Number));
+
+        let (target, changes) = diff(&local, &existing, &BTreeMap::new()).unwrap();
+        assert!(
+            matches!(&changes[0], CollectionChange::ChangeField { to, .. } if to.id == "f_title")
+        );
+        assert!(matches!(&changes[1], CollectionChange::AddField(field) if field.name == "views"));
+        assert_eq!(changes.len(), 2);
+        // System fields are kept even though the schema doesn't list them
+        assert_eq!(
+            target
+                .fields
+                .iter()
+                .map(|field| field.name.as_str())
+                .collect::<Vec<_>>(),
+            ["id", "title", "views"]
+        );
+        assert_eq!(target.fields[1].options["required"], json!(true));
+
+        let changes = diff_only(&CollectionModel::base("posts"), &existing);
+        assert!(
+            matches!(&changes[..], [CollectionChange::RemoveField(field)] if field.name == "title")
+        );
+        assert!(changes[0].is_destructive());
+    }
+
+    #[test]
+    fn field_type_change_is_a_replace() {
+        let existing = remote("posts");
+        let local =
+            CollectionModel::base("posts").field(CollectionField::new("title", FieldType::Number));
+
+        let (target, changes) = diff(&local, &existing, &BTreeMap::new()).unwrap();
+        assert!(
+            matches!(&changes[..], [CollectionChange::ReplaceField { from, to }]
+            if from.ty == FieldType::Text && to.ty == FieldType::Number)
+        );
+        assert!(changes[0].is_destructive());
+        assert!(changes[0].to_string().contains("values are deleted"));
+        // The replacement gets a new id so the server drops the old column
+        assert!(target.get_field("title").unwrap().id.is_empty());
+    }
+
+    #[test]
+    fn collection_type_change_is_refused() {
+        let mut local = remote("posts");
+        local.ty = CollectionType::Auth;
+        assert!(diff(&local, &remote("posts"), &BTreeMap::new()).is_err());
+    }
+
+    #[test]
+    fn rule_changes() {
+        let mut existing = remote("posts");
+        existing.list_rule = Some(String::new());
+        let mut local = existing.clone();
+        local.list_rule = None;
+        local.view_rule = Some("@request.auth.id != ''".to_string());
+
+        let (target, changes) = diff(&local, &existing, &BTreeMap::new()).unwrap();
+        assert_eq!(
+            changes,
+            [
+                CollectionChange::Rule {
+                    rule: "listRule",
+                    from: Some(String::new()),
+                    to: None,
+                },
+                CollectionChange::Rule {
+                    rule: "viewRule",
+                    from: None,
+                    to: Some("@request.auth.id != ''".to_string()),
+                },
+            ]
+        );
+        assert_eq!(target.list_rule, None);
+        assert_eq!(target.view_rule, local.view_rule);
+    }
+
+    #[test]
+    fn index_changes_keep_managed_auth_indexes() {
+        let managed = "CREATE UNIQUE INDEX `idx_tokenKey_users` ON `users` (`tokenKey`)";
+        let mut existing = remote("users");
+        existing.ty = CollectionType::Auth;
+        existing.indexes = vec![
+            managed.to_string(),
+            "CREATE INDEX old ON users (title)".to_string(),
+        ];
+        let mut local = existing.clone();
+        local.indexes = vec!["CREATE INDEX new ON users (title)".to_string()];
+
+        let (target, changes) = diff(&local, &existing, &BTreeMap::new()).unwrap();
+        assert_eq!(
+            changes,
+            [
+                CollectionChange::RemoveIndex("CREATE INDEX old ON users (title)".to_string()),
+                CollectionChange::AddIndex("CREATE INDEX new ON users (title)".to_string()),
+            ]
+        );
+        assert_eq!(
+            target.indexes,
+            [managed, "CREATE INDEX new ON users (title)"]
+        );
+    }
+
+    #[test]
+    fn relations_resolve_collection_names() {
+        let author =
+            CollectionField::new("author", FieldType::Relation).option("collectionId", "users");
+        let tags = CollectionField::new("tags", FieldType::Relation).option("collectionId", "tags");
+        let schema = Schema::new()
+            .collection(CollectionModel::base("tags"))
+            .collection(CollectionModel::base("posts").field(author).field(tags));
+
+        let plan = plan(&schema, vec![remote("users")]).unwrap();
+        let [Change::Create(tags), Change::Create(posts)] = &plan.changes[..] else {
+            panic!("{plan}");
+        };
+        assert_eq!(tags.id, collection_id("tags"));
+        assert_eq!(
+            posts.get_field("author").unwrap().options["collectionId"],
+            json!("id_users")
+        );
+        assert_eq!(
+            posts.get_field("tags").unwrap().options["collectionId"],
+            json!(tags.id)
+        );
+    }
+
+    #[test]
+    fn plan_collections_are_the_final_set() {
+        let mut local = remote("posts");
+        local.list_rule = Some(String::new());
+        let schema = Schema::new()
+            .collection(local)
+            .collection(CollectionModel::base("tags"))
+            .delete_missing(true);
+
+        let plan = plan(&schema, vec![remote("posts"), remote("comments")]).unwrap();
+        assert!(plan.is_destructive());
+        let collections = plan.collections();
+        assert_eq!(
+            collections
+                .iter()
+                .map(|collection| collection.name.as_str())
+                .collect::<Vec<_>>(),
+            ["posts", "tags"]
+        );
+        assert_eq!(collections[0].list_rule, Some(String::new()));
+    }
+}
@@ -0,0 +1,73 @@
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Patch(Map<String, Value>);
+
+impl Patch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(mut self, field: impl std::fmt::Display, value: impl Into<Value>) -> Self {
+        self.0.insert(field.to_string(), value.into());
+        self
+    }
+
+    pub fn increment(mut self, field: impl std::fmt::Display, amount: impl Into<Value>) -> Self {
+        self.0.insert(format!("{field}+"), amount.into());
+        self
+    }
+
+    pub fn decrement(mut self, field: impl std::fmt::Display, amount: impl Into<Value>) -> Self {
+        self.0.insert(format!("{field}-"), amount.into());
+        self
+    }
+
+    pub fn append(mut self, field: impl std::fmt::Display, values: impl Into<Value>) -> Self {
+        self.0.insert(format!("{field}+"), values.into());
+        self
+    }
+
+    pub fn prepend(mut self, field: impl std::fmt::Display, values: impl Into<Value>) -> Self {
+        self.0.insert(format!("+{field}"), values.into());
+        self
+    }
+
+    pub fn remove(mut self, field: impl std::fmt::Display, values: impl Into<Value>) -> Self {
+        self.0.insert(format!("{field}-"), values.into());
+        self
+    }
+
+    pub fn remove_files<S: Into<String>>(
+        mut self,
+        field: impl std::fmt::Display,
+        names: impl IntoIterator<Item = S>,
+    ) -> Self {
+        let names = names.into_iter().map(|name| Value::String(name.into())).collect();
+        self.0.insert(format!("{field}-"), Value::Array(names));
+        self
+    }
+
+    /// Reset a field to its zero value, e.g. removing all files or relations.
+    pub fn clear(mut self, field: impl std::fmt::Display) -> Self {
+        self.0.insert(field.to_string(), Value::Null);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Serialize for Patch {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl From<Patch> for Value {
+    fn from(value: Patch) -> Self {
+        Value::Object(value.0)
+    }
+}
@@ -0,0 +1,263 @@
+use futures_util::{Stream, TryStreamExt, stream};
+use serde::de::DeserializeOwned;
+
+use crate::{Error, ListOptions, Paginated, client::PocketBaseClient, collection::CollectionBuilder};
+
+/// The largest `perPage` PocketBase accepts; larger values are capped by the server.
+pub(crate) const MAX_PER_PAGE: usize = 1000;
+
+/// Entry point for a sort order, e.g. `Sort::desc("created").asc("title")`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort;
+
+impl Sort {
+    pub fn asc(field: impl std::fmt::Display) -> SortBy {
+        SortBy::default().asc(field)
+    }
+
+    pub fn desc(field: impl std::fmt::Display) -> SortBy {
+        SortBy::default().desc(field)
+    }
+
+    pub fn random() -> SortBy {
+        SortBy::default().random()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SortBy(Vec<String>);
+
+impl SortBy {
+    pub fn asc(mut self, field: impl std::fmt::Display) -> Self {
+        self.0.push(field.to_string());
+        self
+    }
+
+    pub fn desc(mut self, field: impl std::fmt::Display) -> Self {
+        self.0.push(format!("-{field}"));
+        self
+    }
+
+    pub fn random(mut self) -> Self {
+        self.0.push("@random".to_string());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl std::fmt::Display for SortBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0.join(","))
+    }
+}
+
+impl From<SortBy> for String {
+    fn from(value: SortBy) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Expand(Vec<String>);
+
+impl Expand {
+    pub fn new(relation: impl std::fmt::Display) -> Self {
+        Self(vec![relation.to_string()])
+    }
+
+    /// Expand another relation, with nested relations given as a dotted path like `author.team`.
+    pub fn nested(mut self, path: impl std::fmt::Display) -> Self {
+        self.0.push(path.to_string());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl std::fmt::Display for Expand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0.join(","))
+    }
+}
+
+impl From<Expand> for String {
+    fn from(value: Expand) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fields(Vec<String>);
+
+impl Fields {
+    pub fn select<F: std::fmt::Display>(fields: impl IntoIterator<Item = F>) -> Self {
+        Self(fields.into_iter().map(|field| field.to_string()).collect())
+    }
+
+    pub fn all() -> Self {
+        Self(vec!["*".to_string()])
+    }
+
+    pub fn field(mut self, field: impl std::fmt::Display) -> Self {
+        self.0.push(field.to_string());
+        self
+    }
+
+    pub fn excerpt(mut self, field: impl std::fmt::Display, max_length: usize, ellipsis: bool) -> Self {
+        self.0.push(format!("{field}:excerpt({max_length},{ellipsis})"));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl std::fmt::Display for Fields {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0.join(","))
+    }
+}
+
+impl From<Fields> for String {
+    fn from(value: Fields) -> Self {
+        value.to_string()
+    }
+}
+
+pub struct Query<'c, P: PocketBaseClient, I: std::fmt::Display> {
+    pub(crate) collection: CollectionBuilder<'c, P, I>,
+    pub(crate) options: ListOptions,
+}
+
+impl<'c, P, I> Query<'c, P, I>
+where
+    P: PocketBaseClient,
+    I: std::fmt::Display,
+{
+    pub fn filter(mut self, filter: impl std::fmt::Display) -> Self {
+        self.options.filter = Some(filter.to_string());
+        self
+    }
+
+    pub fn sort(mut self, sort: impl std::fmt::Display) -> Self {
+        self.options.sort = Some(sort.to_string());
+        self
+    }
+
+    pub fn expand(mut self, expand: impl std::fmt::Display) -> Self {
+        self.options.expand = Some(expand.to_string());
+        self
+    }
+
+    pub fn fields(mut self, fields: impl std::fmt::Display) -> Self {
+        self.options.fields = Some(fields.to_string());
+        self
+    }
+
+    pub fn page(mut self, page: usize) -> Self {
+        self.options.page = Some(page);
+        self
+    }
+
+    pub fn per_page(mut self, per_page: usize) -> Self {
+        self.options.per_page = Some(per_page);
+        self
+    }
+
+    pub fn skip_total(mut self, skip_total: bool) -> Self {
+        self.options.skip_total = Some(skip_total);
+        self
+    }
+
+    pub fn options(&self) -> &ListOptions {
+        &self.options
+    }
+
+    pub async fn fetch<T: DeserializeOwned>(self) -> Result<Paginated<T>, Error> {
+        self.collection.get_list(self.options).await
+    }
+
+    pub async fn first<T: DeserializeOwned>(mut self) -> Result<T, Error> {
+        let filter = self.options.filter.take().unwrap_or_default();
+        self.collection.get_first(filter, self.options).await
+    }
+
+    pub fn stream<T: DeserializeOwned>(self) -> impl Stream<Item = Result<T, Error>> {
+        self.collection.stream(self.options)
+    }
+}
+
+/// Walk every page of a list endpoint, starting at `options.page`.
+///
+/// The last page is detected from the page size the server reports rather than the requested one,
+/// since the server caps `perPage`.
+pub(crate) fn paginate<T, F, Fut>(
+    mut options: ListOptions,
+    default_per_page: usize,
+    fetch: F,
+) -> impl Stream<Item = Result<T, Error>>
+where
+    F: Fn(ListOptions) -> Fut,
+    Fut: Future<Output = Result<Paginated<T>, Error>>,
+{
+    let page = options.page.unwrap_or(1);
+    options.per_page = Some(
+        options
+            .per_page
+            .unwrap_or(default_per_page)
+            .clamp(1, MAX_PER_PAGE),
+    );
+    options.skip_total = Some(options.skip_total.unwrap_or(true));
+
+    stream::try_unfold(
+        (fetch, options, Some(page)),
+        |(fetch, mut options, page)| async move {
+            let Some(page) = page else {
+                return Ok::<_, Error>(None);
+            };
+
+            options.page = Some(page);
+            let result = fetch(options.clone()).await?;
+            let items = result.items.unwrap_or_default();
+            let more = if result.total_pages >= 0 {
+                (page as i64) < result.total_pages
+            } else {
+                !items.is_empty() && items.len() >= result.per_page.max(1)
+            };
+
+            let items = stream::iter(items.into_iter().map(Ok));
+            Ok(Some((items, (fetch, options, more.then_some(page + 1)))))
+        },
+    )
+    .try_flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_sort() {
+        assert_eq!(Sort::desc("created").asc("title").to_string(), "-created,title");
+        assert_eq!(Sort::asc("title").random().to_string(), "title,@random");
+    }
+
+    #[test]
+    fn renders_expand() {
+        let expand = Expand::new("author").nested("author.team");
+        assert_eq!(expand.to_string(), "author,author.team");
+    }
+
+    #[test]
+    fn renders_fields() {
+        let fields = Fields::select(["id", "title"]).excerpt("body", 200, true);
+        assert_eq!(fields.to_string(), "id,title,body:excerpt(200,true)");
+        assert_eq!(Fields::all().to_string(), "*");
+    }
+}
@@ -0,0 +1,346 @@
+use std::{
+    collections::BTreeSet,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use futures_util::{
+    Stream, StreamExt,
+    stream::{self, BoxStream},
+};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+
+use crate::{Error, client::PocketBaseClient};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, strum::Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Action {
+    Create,
+    Update,
+    Delete,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RealtimeEvent<T> {
+    pub action: Action,
+    pub record: T,
+}
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ServerEvent {
+    pub event: String,
+    pub data: String,
+}
+
+impl ServerEvent {
+    fn parse(frame: &str) -> Option<Self> {
+        let mut event = ServerEvent::default();
+        let mut has_data = false;
+
+        for line in frame.lines() {
+            if line.is_empty() || line.starts_with(':') {
+                continue;
+            }
+
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => event.event = value.to_string(),
+                "data" => {
+                    if has_data {
+                        event.data.push('\n');
+                    }
+                    event.data.push_str(value);
+                    has_data = true;
+                }
+                _ => {}
+            }
+        }
+
+        (has_data || !event.event.is_empty()).then_some(event)
+    }
+}
+
+pub(crate) struct EventStream {
+    body: BoxStream<'static, reqwest::Result<Bytes>>,
+    buffer: Vec<u8>,
+}
+
+impl EventStream {
+    pub(crate) fn new(response: reqwest::Response) -> Self {
+        Self {
+            body: response.bytes_stream().boxed(),
+            buffer: Vec::new(),
+        }
+    }
+
+    pub(crate) async fn next_event(&mut self) -> Option<Result<ServerEvent, Error>> {
+        loop {
+            while let Some(frame) = self.take_frame() {
+                if let Some(event) = ServerEvent::parse(&String::from_utf8_lossy(&frame)) {
+                    return Some(Ok(event));
+                }
+            }
+
+            match self.body.next().await {
+                Some(Ok(chunk)) => self
+                    .buffer
+                    .extend(chunk.iter().copied().filter(|byte| *byte != b'\r')),
+                Some(Err(err)) => return Some(Err(err.into())),
+                None => return None,
+            }
+        }
+    }
+
+    fn take_frame(&mut self) -> Option<Vec<u8>> {
+        let end = self.buffer.windows(2).position(|window| window == b"\n\n")?;
+        let frame = self.buffer.drain(..end + 2).collect();
+        Some(frame)
+    }
+}
+
+pub(crate) async fn connect<P: PocketBaseClient>(
+    pocketbase: &P,
+) -> Result<(String, EventStream), Error> {
+    let request = pocketbase
+        .get("/api/realtime")?
+        .header("Accept", "text/event-stream");
+    let res = pocketbase.send(request).await?;
+
+    if !res.status().is_success() {
+        return Err(Error::from_response(res).await);
+    }
+
+    let mut events = EventStream::new(res);
+    match events.next_event().await {
+        Some(Ok(ServerEvent { event, data, .. })) if event == "PB_CONNECT" => {
+            #[derive(Deserialize)]
+            #[serde(rename_all = "camelCase")]
+            struct Connect {
+                client_id: String,
+            }
+
+            let Connect { client_id } = serde_json::from_str(&data)?;
+            Ok((client_id, events))
+        }
+        Some(Err(err)) => Err(err),
+        Some(Ok(_)) | None => Err(Error::custom(
+            "realtime connection closed before the PB_CONNECT handshake",
+        )),
+    }
+}
+
+pub(crate) async fn submit<P: PocketBaseClient>(
+    pocketbase: &P,
+    client_id: &str,
+    topics: impl IntoIterator<Item = impl AsRef<str>>,
+) -> Result<(), Error> {
+    let subscriptions = topics
+        .into_iter()
+        .map(|topic| topic.as_ref().to_string())
+        .collect::<Vec<_>>();
+
+    let request = pocketbase
+        .post("/api/realtime")?
+        .json(&json!({
+            "clientId": client_id,
+            "subscriptions": subscriptions,
+        }));
+    let res = pocketbase.send(request).await?;
+
+    if !res.status().is_success() {
+        return Err(Error::from_response(res).await);
+    }
+    Ok(())
+}
+
+pub struct Subscription<T> {
+    topic: String,
+    inner: BoxStream<'static, Result<RealtimeEvent<T>, Error>>,
+}
+
+impl<T: DeserializeOwned + Send + 'static> Subscription<T> {
+    pub(crate) fn new(topic: String, events: EventStream) -> Self {
+        let name = topic.clone();
+        let inner = stream::unfold(events, move |mut events| {
+            let topic = name.clone();
+            async move {
+                loop {
+                    match events.next_event().await? {
+                        Ok(event) if event.event == topic => {
+                            let record = serde_json::from_str::<RealtimeEvent<T>>(&event.data)
+                                .map_err(Error::from);
+                            return Some((record, events));
+                        }
+                        Ok(_) => continue,
+                        Err(err) => return Some((Err(err), events)),
+                    }
+                }
+            }
+        })
+        .boxed();
+
+        Self {
+            topic,
+            inner,
+        }
+    }
+}
+
+impl<T> Subscription<T> {
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+}
+
+impl<T> Stream for Subscription<T> {
+    type Item = Result<RealtimeEvent<T>, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RealtimeMessage<T = Value> {
+    Event {
+        topic: String,
+        event: RealtimeEvent<T>,
+    },
+    Reconnected {
+        client_id: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    /// How many reconnect attempts to make once the connection is lost, `None` retries forever
+    pub retries: Option<usize>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(250),
+            max: Duration::from_secs(30),
+            retries: None,
+        }
+    }
+}
+
+pub struct Realtime<'c, P: PocketBaseClient> {
+    pub(crate) pocketbase: &'c P,
+    pub(crate) topics: BTreeSet<String>,
+    pub(crate) backoff: Backoff,
+}
+
+impl<'c, P: PocketBaseClient + Sync> Realtime<'c, P> {
+    pub fn subscribe(mut self, topic: impl std::fmt::Display) -> Self {
+        self.topics.insert(topic.to_string());
+        self
+    }
+
+    pub fn unsubscribe(mut self, topic: impl std::fmt::Display) -> Self {
+        self.topics.remove(&topic.to_string());
+        self
+    }
+
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn topics(&self) -> impl Iterator<Item = &str> {
+        self.topics.iter().map(String::as_str)
+    }
+
+    pub async fn listen<T: DeserializeOwned + Send + 'static>(
+        self,
+    ) -> Result<RealtimeStream<'c, T>, Error> {
+        let (_, events) = self.connect().await?;
+
+        let inner = stream::unfold(
+            (self, Connection::Open(events)),
+            |(realtime, mut connection)| async move {
+                loop {
+                    let events = match &mut connection {
+                        Connection::Open(events) => events,
+                        Connection::Lost => match realtime.reconnect().await {
+                            Ok((client_id, events)) => {
+                                let message = RealtimeMessage::Reconnected { client_id };
+                                return Some((Ok(message), (realtime, Connection::Open(events))));
+                            }
+                            Err(err) => return Some((Err(err), (realtime, Connection::Closed))),
+                        },
+                        Connection::Closed => return None,
+                    };
+
+                    match events.next_event().await {
+                        Some(Ok(event)) if realtime.topics.contains(&event.event) => {
+                            let message = serde_json::from_str::<RealtimeEvent<T>>(&event.data)
+                                .map(|record| RealtimeMessage::Event {
+                                    topic: event.event,
+                                    event: record,
+                                })
+                                .map_err(Error::from);
+                            return Some((message, (realtime, connection)));
+                        }
+                        Some(Ok(_)) => continue,
+                        Some(Err(_)) | None => connection = Connection::Lost,
+                    }
+                }
+            },
+        )
+        .boxed();
+
+        Ok(RealtimeStream { inner })
+    }
+
+    async fn connect(&self) -> Result<(String, EventStream), Error> {
+        let (client_id, events) = connect(self.pocketbase).await?;
+        submit(self.pocketbase, &client_id, &self.topics).await?;
+        Ok((client_id, events))
+    }
+
+    async fn reconnect(&self) -> Result<(String, EventStream), Error> {
+        let mut delay = self.backoff.initial;
+        let mut attempt = 0;
+        let mut error = Error::custom("realtime connection lost");
+        while self.backoff.retries.is_none_or(|retries| attempt < retries) {
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+            match self.connect().await {
+                Ok(connection) => return Ok(connection),
+                Err(err) => {
+                    error = err;
+                    delay = (delay * 2).min(self.backoff.max);
+                }
+            }
+        }
+        Err(error)
+    }
+}
+
+enum Connection {
+    Open(EventStream),
+    Lost,
+    Closed,
+}
+
+pub struct RealtimeStream<'c, T> {
+    inner: BoxStream<'c, Result<RealtimeMessage<T>, Error>>,
+}
+
+impl<T> Stream for RealtimeStream<'_, T> {
+    type Item = Result<RealtimeMessage<T>, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
@@ -0,0 +1,174 @@
+use std::ops::{Deref, DerefMut};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub trait PocketRecord {
+    const COLLECTION: &'static str;
+}
+
+pub mod datetime {
+    use chrono::{DateTime, NaiveDateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub const FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3fZ";
+
+    pub fn format(value: &DateTime<Utc>) -> String {
+        value.format(FORMAT).to_string()
+    }
+
+    pub fn parse(value: &str) -> Option<DateTime<Utc>> {
+        let trimmed = value.trim_end_matches('Z');
+        NaiveDateTime::parse_from_str(trimmed, "%Y-%m-%d %H:%M:%S%.f")
+            .map(|value| value.and_utc())
+            .or_else(|_| DateTime::parse_from_rfc3339(value).map(|value| value.to_utc()))
+            .ok()
+    }
+
+    pub fn serialize<S: Serializer>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format(value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        parse(&value).ok_or_else(|| D::Error::custom(format!("invalid pocketbase datetime `{value}`")))
+    }
+
+    pub mod option {
+        use chrono::{DateTime, Utc};
+        use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+        pub fn serialize<S: Serializer>(
+            value: &Option<DateTime<Utc>>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match value {
+                Some(value) => super::serialize(value, serializer),
+                None => serializer.serialize_str(""),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<DateTime<Utc>>, D::Error> {
+            match Option::<String>::deserialize(deserializer)? {
+                None => Ok(None),
+                Some(value) if value.is_empty() => Ok(None),
+                Some(value) => super::parse(&value)
+                    .map(Some)
+                    .ok_or_else(|| D::Error::custom(format!("invalid pocketbase datetime `{value}`"))),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordMeta {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub collection_id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub collection_name: String,
+    #[serde(default, with = "datetime::option", skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Utc>>,
+    #[serde(default, with = "datetime::option", skip_serializing_if = "Option::is_none")]
+    pub updated: Option<DateTime<Utc>>,
+}
+
+pub type BaseRecord = RecordMeta;
+
+/// A record whose system fields are kept in [`RecordMeta`] and whose own fields are `T`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TypedRecord<T> {
+    #[serde(flatten)]
+    pub meta: RecordMeta,
+    #[serde(flatten)]
+    pub fields: T,
+}
+
+impl<T> TypedRecord<T> {
+    pub fn new(fields: T) -> Self {
+        Self {
+            meta: RecordMeta::default(),
+            fields,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.meta.id
+    }
+
+    pub fn into_inner(self) -> T {
+        self.fields
+    }
+}
+
+impl<T> Deref for TypedRecord<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.fields
+    }
+}
+
+impl<T> DerefMut for TypedRecord<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.fields
+    }
+}
+
+impl<T: PocketRecord> PocketRecord for TypedRecord<T> {
+    const COLLECTION: &'static str = T::COLLECTION;
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn datetime_round_trips_pocketbase_format() {
+        let value = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap() + chrono::TimeDelta::milliseconds(678);
+        assert_eq!(datetime::format(&value), "2024-01-02 03:04:05.678Z");
+        assert_eq!(datetime::parse("2024-01-02 03:04:05.678Z"), Some(value));
+        assert_eq!(datetime::parse("2024-01-02T03:04:05.678Z"), Some(value));
+        assert_eq!(datetime::parse("2024-01-02"), None);
+        assert_eq!(datetime::parse(""), None);
+    }
+
+    #[test]
+    fn empty_datetime_is_none() {
+        let meta = serde_json::from_value::<RecordMeta>(json!({
+            "id": "r1",
+            "created": "2024-01-02 03:04:05.000Z",
+            "updated": "",
+        }))
+        .unwrap();
+        assert_eq!(meta.created, Some(Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap()));
+        assert_eq!(meta.updated, None);
+
+        assert!(serde_json::from_value::<RecordMeta>(json!({ "created": "yesterday" })).is_err());
+        assert_eq!(
+            serde_json::to_value(RecordMeta { id: "r1".to_string(), ..meta }).unwrap(),
+            json!({ "id": "r1", "created": "2024-01-02 03:04:05.000Z" })
+        );
+    }
+
+    #[test]
+    fn typed_record_splits_system_fields() {
+        let record = serde_json::from_value::<TypedRecord<serde_json::Map<String, serde_json::Value>>>(json!({
+            "id": "r1",
+            "collectionName": "posts",
+            "title": "hello",
+        }))
+        .unwrap();
+        assert_eq!(record.id(), "r1");
+        assert_eq!(record.meta.collection_name, "posts");
+        assert_eq!(record.get("title"), Some(&json!("hello")));
+        assert!(record.get("id").is_none());
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError, RwLock},
+};
+
+use crate::{Error, Token};
+
+pub type AuthListener = Box<dyn Fn(Option<&Token>) + Send + Sync>;
+
+pub trait AuthStore: Send + Sync {
+    fn load(&self) -> Result<Option<Token>, Error>;
+    fn save(&self, token: &Token) -> Result<(), Error>;
+    fn clear(&self) -> Result<(), Error>;
+    fn on_change(&self, listener: AuthListener);
+}
+
+#[derive(Default)]
+struct Listeners(Mutex<Vec<AuthListener>>);
+
+impl Listeners {
+    fn push(&self, listener: AuthListener) {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).push(listener);
+    }
+
+    fn notify(&self, token: Option<&Token>) {
+        for listener in self.0.lock().unwrap_or_else(PoisonError::into_inner).iter() {
+            listener(token);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryStore {
+    token: RwLock<Option<Token>>,
+    listeners: Listeners,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl AuthStore for MemoryStore {
+    fn load(&self) -> Result<Option<Token>, Error> {
+        Ok(self.token.read().unwrap_or_else(PoisonError::into_inner).clone())
+    }
+
+    fn save(&self, token: &Token) -> Result<(), Error> {
+        *self.token.write().unwrap_or_else(PoisonError::into_inner) = Some(token.clone());
+        self.listeners.notify(Some(token));
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), Error> {
+        *self.token.write().unwrap_or_else(PoisonError::into_inner) = None;
+        self.listeners.notify(None);
+        Ok(())
+    }
+
+    fn on_change(&self, listener: AuthListener) {
+        self.listeners.push(listener);
+    }
+}
+
+pub struct FileStore {
+    path: PathBuf,
+    listeners: Listeners,
+}
+
+impl FileStore {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            listeners: Default::default(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AuthStore for FileStore {
+    fn load(&self) -> Result<Option<Token>, Error> {
+        match std::fs::read(&self.path) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn save(&self, token: &Token) -> Result<(), Error> {
+        if let Some(parent) = self.path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let temp = self.path.with_extension("tmp");
+        let mut file = create_private(&temp)?;
+        file.write_all(&serde_json::to_vec_pretty(token).map_err(Error::encode)?)?;
+        file.sync_all()?;
+        std::fs::rename(&temp, &self.path)?;
+
+        self.listeners.notify(Some(token));
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), Error> {
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        self.listeners.notify(None);
+        Ok(())
+    }
+
+    fn on_change(&self, listener: AuthListener) {
+        self.listeners.push(listener);
+    }
+}
+
+/// The token is a bearer credential, so the file is only readable by its owner.
+fn create_private(path: &Path) -> std::io::Result<std::fs::File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        options.mode(0o600);
+        let file = options.open(path)?;
+        // `mode` only applies when the file is created, not to a leftover temp file
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        Ok(file)
+    }
+
+    #[cfg(not(unix))]
+    options.open(path)
+}
@@ -0,0 +1,164 @@
+mod common;
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use chrono::{TimeDelta, Utc};
+use futures_util::future::join_all;
+use jsonwebtoken::{EncodingKey, Header};
+use pocket::{AuthorizedClient, Token};
+use serde_json::{Value, json};
+
+use common::{Response, serve};
+
+fn jwt(id: &str, expires: TimeDelta, ty: &str, secret: &[u8]) -> String {
+    jsonwebtoken::encode(
+        &Header::default(),
+        &json!({
+            "id": id,
+            "collectionId": "_pb_users_auth_",
+            "exp": (Utc::now() + expires).timestamp(),
+            "refreshable": true,
+            "type": ty,
+        }),
+        &EncodingKey::from_secret(secret),
+    )
+    .unwrap()
+}
+
+fn token(auth: String, expires: TimeDelta) -> Token {
+    Token {
+        collection: "users".to_string(),
+        user: "u1".to_string(),
+        auth,
+        expires: Utc::now() + expires,
+        refreshable: true,
+        ty: "auth".to_string(),
+        record: None,
+    }
+}
+
+/// A server whose records only accept `fresh` and whose `auth-refresh` hands it out.
+async fn refreshing_server(fresh: String, refreshes: Arc<AtomicUsize>) -> String {
+    serve(move |request| {
+        let fresh = fresh.clone();
+        let refreshes = refreshes.clone();
+        async move {
+            if request.path == "/api/collections/users/auth-refresh" {
+                refreshes.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                return Response::json(200, json!({ "token": fresh, "record": { "id": "u1" } }));
+            }
+
+            match request.header("authorization") == Some(fresh.as_str()) {
+                true => Response::json(200, json!({ "id": "1" })),
+                false => Response::json(
+                    401,
+                    json!({ "status": 401, "message": "The request requires valid record authorization token.", "data": {} }),
+                ),
+            }
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+async fn unauthorized_requests_share_one_refresh_and_retry() {
+    let fresh = jwt("u1", TimeDelta::hours(2), "auth", b"secret");
+    let refreshes = Arc::new(AtomicUsize::new(0));
+    let base_uri = refreshing_server(fresh.clone(), refreshes.clone()).await;
+
+    let stale = jwt("u1", TimeDelta::hours(1), "auth", b"secret");
+    let client = AuthorizedClient::new(base_uri, token(stale, TimeDelta::hours(1)))
+        .auto_refresh(TimeDelta::minutes(1));
+
+    let results = join_all((0..4).map(|_| {
+        let client = client.clone();
+        tokio::spawn(async move {
+            client
+                .collection("posts")
+                .get_one::<Value>("1", Default::default())
+                .await
+        })
+    }))
+    .await;
+
+    for result in results {
+        assert_eq!(result.unwrap().unwrap(), json!({ "id": "1" }));
+    }
+    assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+    assert_eq!(client.token().auth, fresh);
+}
+
+#[tokio::test]
+async fn refreshes_before_the_token_expires() {
+    let fresh = jwt("u1", TimeDelta::hours(2), "auth", b"secret");
+    let refreshes = Arc::new(AtomicUsize::new(0));
+    let base_uri = refreshing_server(fresh.clone(), refreshes.clone()).await;
+
+    let expiring = jwt("u1", TimeDelta::seconds(30), "auth", b"secret");
+    let client = AuthorizedClient::new(base_uri, token(expiring, TimeDelta::seconds(30)))
+        .auto_refresh(TimeDelta::minutes(1));
+
+    let record = client
+        .collection("posts")
+        .get_one::<Value>("1", Default::default())
+        .await
+        .unwrap();
+
+    assert_eq!(record, json!({ "id": "1" }));
+    assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+    assert_eq!(client.token().auth, fresh);
+}
+
+#[tokio::test]
+async fn mfa_challenge_is_not_retried() {
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let base_uri = serve({
+        let attempts = attempts.clone();
+        move |request| {
+            let attempts = attempts.clone();
+            async move {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                assert_eq!(request.path, "/api/collections/users/auth-with-password");
+                Response::json(401, json!({ "mfaId": format!("mfa{}", attempts.load(Ordering::SeqCst)) }))
+            }
+        }
+    })
+    .await;
+
+    let client = AuthorizedClient::new(
+        base_uri,
+        token(jwt("u1", TimeDelta::hours(1), "auth", b"secret"), TimeDelta::hours(1)),
+    )
+    .auto_refresh(TimeDelta::minutes(1));
+
+    let Err(err) = client
+        .collection("users")
+        .auth_with_password("u1@example.com", "password")
+        .await
+    else {
+        panic!("expected an mfa challenge");
+    };
+
+    assert_eq!(err.mfa_id(), Some("mfa1"));
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn verify_checks_signature_expiry_and_type() {
+    let valid = token(jwt("u1", TimeDelta::hours(1), "auth", b"secret"), TimeDelta::hours(1));
+    let claims = valid.verify("secret").unwrap();
+    assert_eq!(claims.id, "u1");
+    assert_eq!(claims.collection_id, "_pb_users_auth_");
+
+    assert!(valid.verify("other").is_err());
+
+    let expired = token(jwt("u1", TimeDelta::hours(-1), "auth", b"secret"), TimeDelta::hours(-1));
+    assert!(expired.verify("secret").is_err());
+
+    let file = token(jwt("u1", TimeDelta::hours(1), "file", b"secret"), TimeDelta::hours(1));
+    assert!(file.verify("secret").is_err());
+}
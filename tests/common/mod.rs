@@ -0,0 +1,161 @@
+#![allow(dead_code)]
+
+use std::{future::Future, sync::Arc};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap_or_default()
+    }
+}
+
+pub enum Body {
+    Full(Vec<u8>),
+    Stream(mpsc::UnboundedReceiver<Vec<u8>>),
+}
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Body,
+}
+
+impl Response {
+    pub fn json(status: u16, body: impl ToString) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: Body::Full(body.to_string().into_bytes()),
+        }
+    }
+
+    pub fn no_content() -> Self {
+        Self {
+            status: 204,
+            content_type: "application/json",
+            body: Body::Full(Vec::new()),
+        }
+    }
+
+    pub fn event_stream(chunks: mpsc::UnboundedReceiver<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            content_type: "text/event-stream",
+            body: Body::Stream(chunks),
+        }
+    }
+}
+
+/// Start a one-request-per-connection HTTP server and return its base url.
+pub async fn serve<F, Fut>(handler: F) -> String
+where
+    F: Fn(Request) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Response> + Send,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let handler = Arc::new(handler);
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let mut stream = stream;
+                if let Some(request) = read_request(&mut stream).await {
+                    let response = handler(request).await;
+                    write_response(stream, response).await;
+                }
+            });
+        }
+    });
+
+    format!("http://{addr}")
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 4096];
+    let head_end = loop {
+        if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..head_end]).to_string();
+    let mut lines = head.lines();
+    let mut start = lines.next()?.split(' ');
+    let method = start.next()?.to_string();
+    let path = start.next()?.to_string();
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.to_string(), value.trim().to_string()))
+        .collect::<Vec<_>>();
+    let length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    let mut body = buffer[head_end..].to_vec();
+    while body.len() < length {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+
+    Some(Request {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+async fn write_response(mut stream: TcpStream, response: Response) {
+    let head = format!(
+        "HTTP/1.1 {} OK\r\ncontent-type: {}\r\nconnection: close\r\n",
+        response.status, response.content_type
+    );
+    match response.body {
+        Body::Full(body) => {
+            let head = format!("{head}content-length: {}\r\n\r\n", body.len());
+            let _ = stream.write_all(head.as_bytes()).await;
+            let _ = stream.write_all(&body).await;
+        }
+        Body::Stream(mut chunks) => {
+            let _ = stream.write_all(format!("{head}\r\n").as_bytes()).await;
+            while let Some(chunk) = chunks.recv().await {
+                if stream.write_all(&chunk).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    let _ = stream.shutdown().await;
+}
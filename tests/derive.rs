@@ -0,0 +1,62 @@
+mod common;
+
+use pocket::{Client, PocketRecord, TypedRecord};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use common::{Response, serve};
+
+#[derive(Debug, PocketRecord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[pocket(collection = "blog_posts")]
+struct BlogPost {
+    title: String,
+    view_count: u32,
+}
+
+#[derive(PocketRecord)]
+#[allow(dead_code)]
+struct HTTPLog {
+    status: u16,
+}
+
+#[test]
+fn generates_collection_and_field_names() {
+    assert_eq!(BlogPost::COLLECTION, "blog_posts");
+    assert_eq!(<TypedRecord<BlogPost> as PocketRecord>::COLLECTION, "blog_posts");
+    assert_eq!(HTTPLog::COLLECTION, "http_log");
+
+    assert_eq!(BlogPost::TITLE, "title");
+    assert_eq!(BlogPost::VIEW_COUNT, "viewCount");
+    assert_eq!(BlogPost::ID, "id");
+    assert_eq!(BlogPost::COLLECTION_NAME, "collectionName");
+}
+
+#[tokio::test]
+async fn records_use_the_derived_collection() {
+    let base_uri = serve(|request| async move {
+        assert_eq!(request.path, "/api/collections/blog_posts/records/p1");
+        Response::json(
+            200,
+            json!({
+                "id": "p1",
+                "collectionName": "blog_posts",
+                "created": "2024-01-02 03:04:05.000Z",
+                "title": "hello",
+                "viewCount": 3,
+            }),
+        )
+    })
+    .await;
+
+    let client = Client::new(base_uri);
+    let post = client
+        .records::<BlogPost>()
+        .get_one::<TypedRecord<BlogPost>>("p1", Default::default())
+        .await
+        .unwrap();
+
+    assert_eq!(post.id(), "p1");
+    assert_eq!(post.title, "hello");
+    assert_eq!(post.view_count, 3);
+}
@@ -0,0 +1,196 @@
+mod common;
+
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use futures_util::StreamExt;
+use pocket::{Action, Backoff, Client, RealtimeMessage};
+use serde_json::{Value, json};
+use tokio::sync::mpsc;
+
+use common::{Response, serve};
+
+#[tokio::test]
+async fn subscribe_handshakes_and_filters_events_by_topic() {
+    let stream = Arc::new(Mutex::new(None::<mpsc::UnboundedSender<Vec<u8>>>));
+    let (submitted, mut subscriptions) = mpsc::unbounded_channel();
+
+    let base_uri = serve(move |request| {
+        let stream = stream.clone();
+        let submitted = submitted.clone();
+        async move {
+            match request.method.as_str() {
+                "GET" => {
+                    let (tx, rx) = mpsc::unbounded_channel();
+                    tx.send(b"id:abc\r\nevent:PB_CONNECT\r\ndata:{\"clientId\":\"abc\"}\r\n\r\n".to_vec())
+                        .unwrap();
+                    *stream.lock().unwrap() = Some(tx);
+                    Response::event_stream(rx)
+                }
+                _ => {
+                    submitted.send((request.path.clone(), request.json())).unwrap();
+
+                    let tx = stream.lock().unwrap().clone().unwrap();
+                    tx.send(b": keep-alive\r\n\r\n".to_vec()).unwrap();
+                    tx.send(
+                        b"event:comments/*\r\ndata:{\"action\":\"create\",\"record\":{\"id\":\"c\"}}\r\n\r\n"
+                            .to_vec(),
+                    )
+                    .unwrap();
+                    // Split a frame across chunks, including the `\r\n` pairs
+                    tx.send(b"event:posts/*\r\ndata:{\"action\":\"update\",".to_vec()).unwrap();
+                    tx.send(b"\"record\":{\"id\":\"p\"}}\r".to_vec()).unwrap();
+                    tx.send(b"\n\r\n".to_vec()).unwrap();
+                    Response::no_content()
+                }
+            }
+        }
+    })
+    .await;
+
+    let client = Client::new(base_uri);
+    let mut subscription = client
+        .collection("posts")
+        .subscribe::<Value>("*")
+        .await
+        .unwrap();
+    assert_eq!(subscription.topic(), "posts/*");
+
+    let (path, body) = subscriptions.recv().await.unwrap();
+    assert_eq!(path, "/api/realtime");
+    assert_eq!(body, json!({ "clientId": "abc", "subscriptions": ["posts/*"] }));
+
+    let event = subscription.next().await.unwrap().unwrap();
+    assert_eq!(event.action, Action::Update);
+    assert_eq!(event.record, json!({ "id": "p" }));
+}
+
+#[tokio::test]
+async fn listen_reconnects_and_resubscribes_every_topic() {
+    let streams = Arc::new(Mutex::new(Vec::<Option<mpsc::UnboundedSender<Vec<u8>>>>::new()));
+    let (submitted, mut subscriptions) = mpsc::unbounded_channel();
+
+    let base_uri = serve(move |request| {
+        let streams = streams.clone();
+        let submitted = submitted.clone();
+        async move {
+            let mut streams = streams.lock().unwrap();
+            match request.method.as_str() {
+                "GET" => {
+                    let (tx, rx) = mpsc::unbounded_channel();
+                    let connect = format!(
+                        "event:PB_CONNECT\ndata:{{\"clientId\":\"c{}\"}}\n\n",
+                        streams.len() + 1
+                    );
+                    tx.send(connect.into_bytes()).unwrap();
+                    streams.push(Some(tx));
+                    Response::event_stream(rx)
+                }
+                _ => {
+                    submitted.send(request.json()).unwrap();
+
+                    let connection = streams.len();
+                    let event = format!(
+                        "event:posts/*\ndata:{{\"action\":\"create\",\"record\":{{\"id\":\"p{connection}\"}}}}\n\n"
+                    );
+                    streams[connection - 1].as_ref().unwrap().send(event.into_bytes()).unwrap();
+                    // Close the first connection once its event is sent
+                    if connection == 1 {
+                        streams[0] = None;
+                    }
+                    Response::no_content()
+                }
+            }
+        }
+    })
+    .await;
+
+    let client = Client::new(base_uri);
+    let mut stream = client
+        .realtime()
+        .subscribe("posts/*")
+        .subscribe("users/u1")
+        .backoff(Backoff {
+            initial: Duration::from_millis(10),
+            ..Default::default()
+        })
+        .listen::<Value>()
+        .await
+        .unwrap();
+
+    let topics = json!(["posts/*", "users/u1"]);
+    assert_eq!(
+        subscriptions.recv().await.unwrap(),
+        json!({ "clientId": "c1", "subscriptions": topics })
+    );
+    assert!(matches!(
+        stream.next().await.unwrap().unwrap(),
+        RealtimeMessage::Event { event, .. } if event.record == json!({ "id": "p1" })
+    ));
+
+    assert!(matches!(
+        stream.next().await.unwrap().unwrap(),
+        RealtimeMessage::Reconnected { client_id } if client_id == "c2"
+    ));
+    assert_eq!(
+        subscriptions.recv().await.unwrap(),
+        json!({ "clientId": "c2", "subscriptions": topics })
+    );
+    assert!(matches!(
+        stream.next().await.unwrap().unwrap(),
+        RealtimeMessage::Event { topic, event } if topic == "posts/*" && event.record == json!({ "id": "p2" })
+    ));
+}
+
+async fn reconnect_attempts(retries: usize) -> usize {
+    let connections = Arc::new(AtomicUsize::new(0));
+    let base_uri = serve({
+        let connections = connections.clone();
+        move |request| {
+            let connections = connections.clone();
+            async move {
+                match request.method.as_str() {
+                    "GET" if connections.fetch_add(1, Ordering::SeqCst) == 0 => {
+                        // Closes right after the handshake since the sender is dropped
+                        let (tx, rx) = mpsc::unbounded_channel();
+                        tx.send(b"event:PB_CONNECT\ndata:{\"clientId\":\"c1\"}\n\n".to_vec())
+                            .unwrap();
+                        Response::event_stream(rx)
+                    }
+                    "GET" => Response::json(503, json!({ "status": 503, "message": "unavailable", "data": {} })),
+                    _ => Response::no_content(),
+                }
+            }
+        }
+    })
+    .await;
+
+    let client = Client::new(base_uri);
+    let mut stream = client
+        .realtime()
+        .subscribe("posts/*")
+        .backoff(Backoff {
+            initial: Duration::from_millis(1),
+            max: Duration::from_millis(5),
+            retries: Some(retries),
+        })
+        .listen::<Value>()
+        .await
+        .unwrap();
+
+    assert!(stream.next().await.unwrap().is_err());
+    assert!(stream.next().await.is_none());
+    connections.load(Ordering::SeqCst) - 1
+}
+
+#[tokio::test]
+async fn backoff_retries_counts_reconnect_attempts() {
+    assert_eq!(reconnect_attempts(0).await, 0);
+    assert_eq!(reconnect_attempts(1).await, 1);
+    assert_eq!(reconnect_attempts(3).await, 3);
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use pocket::{Client, Error, Patch, files::File};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use common::{Request, Response, serve};
+use tokio::sync::mpsc;
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Post {
+    id: String,
+    title: String,
+}
+
+#[tokio::test]
+async fn malformed_body_is_a_decode_error() {
+    let base_uri = serve(|_| async { Response::json(200, r#"{"id":"1","title":5}"#) }).await;
+
+    let client = Client::new(base_uri);
+    let err = client
+        .collection("posts")
+        .get_one::<Post>("1", Default::default())
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, Error::Decode(_)), "{err:?}");
+}
+
+fn query(path: &str, key: &str) -> Option<usize> {
+    let (_, query) = path.split_once('?')?;
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(name, _)| *name == key)
+        .and_then(|(_, value)| value.parse().ok())
+}
+
+async fn full_list_with_server_cap(cap: usize) {
+    const TOTAL: usize = 2500;
+
+    let base_uri = serve(move |request| async move {
+        let page = query(&request.path, "page").unwrap_or(1);
+        let per_page = query(&request.path, "perPage").unwrap_or(30).min(cap);
+        let items = ((page - 1) * per_page..(page * per_page).min(TOTAL))
+            .map(|id| json!({ "id": id.to_string() }))
+            .collect::<Vec<_>>();
+
+        Response::json(
+            200,
+            json!({
+                "page": page,
+                "perPage": per_page,
+                "totalItems": -1,
+                "totalPages": -1,
+                "items": items,
+            }),
+        )
+    })
+    .await;
+
+    let client = Client::new(base_uri);
+    let items = tokio::spawn(async move {
+        client
+            .collection("posts")
+            .get_full_list::<Value>(5000, Default::default())
+            .await
+    })
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(items.len(), TOTAL);
+    assert_eq!(items[TOTAL - 1]["id"], json!((TOTAL - 1).to_string()));
+}
+
+#[tokio::test]
+async fn full_list_pages_past_the_per_page_cap() {
+    full_list_with_server_cap(1000).await;
+}
+
+#[tokio::test]
+async fn full_list_follows_the_per_page_reported_by_the_server() {
+    full_list_with_server_cap(400).await;
+}
+
+#[tokio::test]
+async fn unserializable_record_is_an_encode_error() {
+    let base_uri = serve(|_| async { Response::json(200, "{}") }).await;
+
+    // Maps with non-string keys can't be represented as JSON
+    let record = std::collections::BTreeMap::from([((1, 2), "value")]);
+    let err = Client::new(base_uri)
+        .collection("posts")
+        .create::<Value>(record, [], Default::default())
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, Error::Encode(_)), "{err:?}");
+    assert!(!err.is_decode());
+}
+
+async fn capture_update(files: Vec<(String, File)>) -> Request {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let base_uri = serve(move |request| {
+        tx.send(request).unwrap();
+        async { Response::json(200, json!({ "id": "1" })) }
+    })
+    .await;
+
+    let patch = Patch::new()
+        .increment("views", 1)
+        .remove("tags", json!(["old"]))
+        .clear("cover");
+    Client::new(base_uri)
+        .collection("posts")
+        .update::<Value>("1", patch, files, Default::default())
+        .await
+        .unwrap();
+
+    rx.recv().await.unwrap()
+}
+
+#[tokio::test]
+async fn patch_is_sent_as_json() {
+    let request = capture_update(Vec::new()).await;
+
+    assert_eq!(request.method, "PATCH");
+    assert_eq!(request.path, "/api/collections/posts/records/1");
+    assert_eq!(request.header("content-type"), Some("application/json"));
+    assert_eq!(
+        request.json(),
+        json!({ "views+": 1, "tags-": ["old"], "cover": null })
+    );
+}
+
+#[tokio::test]
+async fn files_switch_the_body_to_multipart() {
+    let file = File::raw("notes.txt", "text/plain", b"hello".as_slice());
+    let request = capture_update(vec![("attachments+".to_string(), file)]).await;
+
+    assert!(
+        request
+            .header("content-type")
+            .is_some_and(|value| value.starts_with("multipart/form-data; boundary="))
+    );
+    let body = String::from_utf8_lossy(&request.body);
+    assert!(body.contains("name=\"@jsonPayload\"\r\n\r\n{\"cover\":null,\"tags-\":[\"old\"],\"views+\":1}"), "{body}");
+    assert!(body.contains("name=\"attachments+\"; filename=\"notes.txt\""), "{body}");
+}
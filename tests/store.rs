@@ -0,0 +1,64 @@
+use chrono::{TimeDelta, Utc};
+use pocket::{AuthStore, Client, FileStore, MemoryStore, Token};
+use serde_json::{Value, json};
+
+fn token() -> Token {
+    Token {
+        collection: "users".to_string(),
+        user: "u1".to_string(),
+        auth: "token".to_string(),
+        expires: Utc::now() + TimeDelta::hours(1),
+        refreshable: true,
+        ty: "auth".to_string(),
+        record: Some(json!({ "id": "u1", "email": "u1@example.com" })),
+    }
+}
+
+#[test]
+fn file_store_round_trips_token() {
+    let dir = std::env::temp_dir().join(format!("pocket-store-{}", std::process::id()));
+    let store = FileStore::new(dir.join("auth.json"));
+
+    let token = token();
+    store.save(&token).unwrap();
+    assert_eq!(store.load().unwrap(), Some(token));
+
+    store.clear().unwrap();
+    assert!(store.load().unwrap().is_none());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn resumed_client_exposes_stored_record() {
+    let store = MemoryStore::new();
+    store.save(&token()).unwrap();
+
+    let client = Client::new("http://localhost:8090")
+        .authorize_from_store(store)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        client.record::<Value>().unwrap(),
+        Some(json!({ "id": "u1", "email": "u1@example.com" }))
+    );
+
+    let client = Client::new("http://localhost:8090").authorize(Token {
+        record: None,
+        ..token()
+    });
+    assert!(client.record::<Value>().unwrap().is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn file_store_is_only_readable_by_owner() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("pocket-store-mode-{}", std::process::id()));
+    let store = FileStore::new(dir.join("auth.json"));
+    store.save(&token()).unwrap();
+
+    let mode = std::fs::metadata(store.path()).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    std::fs::remove_dir_all(dir).unwrap();
+}